@@ -0,0 +1,249 @@
+use crate::recursive_filters::Filter;
+
+/// Fixed gain position and velocity tracker, the steady state form of a 2 state Kalman
+/// filter with a constant velocity model. Cheap enough to run without any matrix math.
+///
+/// pos_p = pos + vel * dt,  vel_p = vel                prediction
+/// r = z - pos_p                                       residual
+/// pos = pos_p + alpha * r,  vel = vel_p + beta / dt * r
+///
+/// The first measurement initializes the position, with zero velocity.
+pub struct AlphaBetaFilter {
+    est_pos: f64,
+    est_vel: f64,
+    alpha: f64,
+    beta: f64,
+    dt: f64,
+    initialized: bool,
+}
+
+impl AlphaBetaFilter {
+    /// alpha, beta: position and velocity gains, stable for 0 < alpha < 2 and
+    /// 0 < beta < 4 - 2 * alpha
+    /// dt: sample period [s]
+    pub fn new(alpha: f64, beta: f64, dt: f64) -> Self {
+        assert!(
+            (alpha > 0.0) && (alpha < 2.0) && (beta > 0.0) && (beta < 4.0 - 2.0 * alpha),
+            "AlphaBetaFilter: Gains must satisfy 0 < alpha < 2 and 0 < beta < 4 - 2 * alpha."
+        );
+        assert!(
+            dt > 0.0,
+            "AlphaBetaFilter: Sample period must be greater than zero."
+        );
+
+        Self {
+            est_pos: 0.0,
+            est_vel: 0.0,
+            alpha,
+            beta,
+            dt,
+            initialized: false,
+        }
+    }
+
+    /// Optimal gains for the tracking index, see alpha_beta_gains
+    pub fn from_tracking_index(tracking_index: f64, dt: f64) -> Self {
+        let [alpha, beta] = alpha_beta_gains(tracking_index);
+        Self::new(alpha, beta, dt)
+    }
+
+    pub fn update(&mut self, z: f64) {
+        if !(self.initialized) {
+            self.est_pos = z;
+            self.initialized = true;
+            return;
+        }
+
+        let pred_pos = self.est_pos + self.est_vel * self.dt;
+        let residual = z - pred_pos;
+
+        self.est_pos = pred_pos + self.alpha * residual;
+        self.est_vel += self.beta / self.dt * residual;
+    }
+
+    pub fn get_position(&self) -> f64 {
+        self.est_pos
+    }
+
+    pub fn get_velocity(&self) -> f64 {
+        self.est_vel
+    }
+
+    pub fn get_gains(&self) -> [f64; 2] {
+        [self.alpha, self.beta]
+    }
+}
+
+impl Filter for AlphaBetaFilter {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_position()
+    }
+
+    fn reset(&mut self) {
+        self.est_pos = 0.0;
+        self.est_vel = 0.0;
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// Fixed gain position, velocity and acceleration tracker, the steady state form of a
+/// 3 state Kalman filter with a constant acceleration model. Follows a steadily
+/// accelerating target without the lag of AlphaBetaFilter.
+///
+/// pos_p = pos + vel * dt + acc * dt^2 / 2,  vel_p = vel + acc * dt,  acc_p = acc
+/// r = z - pos_p
+/// pos = pos_p + alpha * r,  vel = vel_p + beta / dt * r,  acc = acc_p + gamma / dt^2 * r
+///
+/// The first measurement initializes the position, with zero velocity and acceleration.
+pub struct AlphaBetaGammaFilter {
+    est_pos: f64,
+    est_vel: f64,
+    est_acc: f64,
+    alpha: f64,
+    beta: f64,
+    gamma: f64,
+    dt: f64,
+    initialized: bool,
+}
+
+impl AlphaBetaGammaFilter {
+    /// alpha, beta, gamma: position, velocity and acceleration gains, all greater than zero
+    /// dt: sample period [s]
+    pub fn new(alpha: f64, beta: f64, gamma: f64, dt: f64) -> Self {
+        assert!(
+            (alpha > 0.0) && (beta > 0.0) && (gamma > 0.0),
+            "AlphaBetaGammaFilter: Gains must be greater than zero."
+        );
+        assert!(
+            dt > 0.0,
+            "AlphaBetaGammaFilter: Sample period must be greater than zero."
+        );
+
+        Self {
+            est_pos: 0.0,
+            est_vel: 0.0,
+            est_acc: 0.0,
+            alpha,
+            beta,
+            gamma,
+            dt,
+            initialized: false,
+        }
+    }
+
+    /// Optimal gains for the tracking index, see alpha_beta_gamma_gains
+    pub fn from_tracking_index(tracking_index: f64, dt: f64) -> Self {
+        let [alpha, beta, gamma] = alpha_beta_gamma_gains(tracking_index);
+        Self::new(alpha, beta, gamma, dt)
+    }
+
+    pub fn update(&mut self, z: f64) {
+        if !(self.initialized) {
+            self.est_pos = z;
+            self.initialized = true;
+            return;
+        }
+
+        let dt = self.dt;
+        let pred_pos = self.est_pos + self.est_vel * dt + 0.5 * self.est_acc * dt.powi(2);
+        let pred_vel = self.est_vel + self.est_acc * dt;
+        let residual = z - pred_pos;
+
+        self.est_pos = pred_pos + self.alpha * residual;
+        self.est_vel = pred_vel + self.beta / dt * residual;
+        self.est_acc += self.gamma / dt.powi(2) * residual;
+    }
+
+    pub fn get_position(&self) -> f64 {
+        self.est_pos
+    }
+
+    pub fn get_velocity(&self) -> f64 {
+        self.est_vel
+    }
+
+    pub fn get_acceleration(&self) -> f64 {
+        self.est_acc
+    }
+
+    pub fn get_gains(&self) -> [f64; 3] {
+        [self.alpha, self.beta, self.gamma]
+    }
+}
+
+impl Filter for AlphaBetaGammaFilter {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_position()
+    }
+
+    fn reset(&mut self) {
+        self.est_pos = 0.0;
+        self.est_vel = 0.0;
+        self.est_acc = 0.0;
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// Optimal [alpha, beta] for a tracking index, Kalata (1984)
+/// tracking_index: lambda = accel_std * dt^2 / meas_std, the ratio of process to
+/// measurement noise, where accel_std is the std dev of the target's random acceleration
+/// r = (4 + lambda - sqrt(8 lambda + lambda^2)) / 4
+/// alpha = 1 - r^2,  beta = 2 (2 - alpha) - 4 sqrt(1 - alpha)
+pub fn alpha_beta_gains(tracking_index: f64) -> [f64; 2] {
+    assert!(
+        tracking_index > 0.0,
+        "alpha_beta_gains: Tracking index must be greater than zero."
+    );
+
+    let lambda = tracking_index;
+    let r = (4.0 + lambda - (8.0 * lambda + lambda.powi(2)).sqrt()) / 4.0;
+    let alpha = 1.0 - r.powi(2);
+    let beta = 2.0 * (2.0 - alpha) - 4.0 * (1.0 - alpha).sqrt();
+
+    [alpha, beta]
+}
+
+/// Optimal [alpha, beta, gamma] for a tracking index, Gray and Murray (1993)
+/// tracking_index: lambda = jerk_std * dt^3 / meas_std, the ratio of process to
+/// measurement noise, where jerk_std is the std dev of the target's random jerk
+/// s is the real root of s^3 + b s^2 + c s - 1 = 0, b = lambda / 2 - 3, c = lambda / 2 + 3
+/// alpha = 1 - s^2,  beta = 2 (1 - s)^2,  gamma = beta^2 / (2 alpha)
+pub fn alpha_beta_gamma_gains(tracking_index: f64) -> [f64; 3] {
+    assert!(
+        tracking_index > 0.0,
+        "alpha_beta_gamma_gains: Tracking index must be greater than zero."
+    );
+
+    // Cardano's formula, after substituting s = z - b / 3
+    let lambda = tracking_index;
+    let b = lambda / 2.0 - 3.0;
+    let c = lambda / 2.0 + 3.0;
+    let d = -1.0;
+    let p = c - b.powi(2) / 3.0;
+    let q = 2.0 * b.powi(3) / 27.0 - b * c / 3.0 + d;
+    let v = (q.powi(2) + 4.0 * p.powi(3) / 27.0).sqrt();
+    let z = -(q / 2.0 + v / 2.0).cbrt() - (q / 2.0 - v / 2.0).cbrt();
+    let s = z - b / 3.0;
+
+    let alpha = 1.0 - s.powi(2);
+    let beta = 2.0 * (1.0 - s).powi(2);
+    let gamma = beta.powi(2) / (2.0 * alpha);
+
+    [alpha, beta, gamma]
+}
@@ -0,0 +1,112 @@
+use crate::sensor_spoofs::PositionSensor;
+use crate::trajectory::{Segment, Trajectory};
+use crate::utils::{ascending_float_range, plot_two_lines, PlotLabels};
+use kalman_filter_for_beginners_rust::alpha_beta_filters::{AlphaBetaFilter, AlphaBetaGammaFilter};
+use kalman_filter_for_beginners_rust::kalman_filter::{KalmanFilter, SystemModel};
+use nalgebra::{DMatrix, DVector};
+
+pub fn alpha_beta_filter_velocity_from_position_example() {
+    // Setup simulation & data logging; inputs based on textbook example
+    const DT: f64 = 0.1;
+    let times_s: Vec<f64> = ascending_float_range(0.0, 10.0, DT);
+
+    let num_data_pts: usize = times_s.len();
+
+    let mut true_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut klmn_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut alpha_beta_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut alpha_beta_gamma_vels = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Kalman filter set up as in kalman_filter_estimate_velocity_from_position_example
+    let system_model = SystemModel::new(
+        DMatrix::from_row_slice(2, 2, &[1.0, DT, 0.0, 1.0]),
+        DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]),
+        DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+        DMatrix::from_row_slice(1, 1, &[10.0]),
+    );
+    let mut klmn_filt = KalmanFilter::new(
+        system_model,
+        DVector::from_column_slice(&[0.0, 20.0]),
+        DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+    );
+
+    // The target cruises at 80 m/s, speeds up to 110 m/s, then weaves, measured with 10 m
+    // noise. Size the alpha-beta filter for the 10 m/s^2 manoeuvres.
+    let trajectory = Trajectory::new(&[0.0], &[80.0])
+        .then(Segment::ConstantVelocity { duration_s: 3.0 })
+        .then(Segment::ConstantAcceleration {
+            duration_s: 3.0,
+            acc: vec![10.0],
+        })
+        .then(Segment::Sinusoid {
+            duration_s: 4.0,
+            amplitude: vec![5.0],
+            freq_hz: 0.25,
+        });
+    let meas_std: f64 = 10.0;
+    let accel_std: f64 = 10.0;
+    let mut alpha_beta_filt =
+        AlphaBetaFilter::from_tracking_index(accel_std * DT.powi(2) / meas_std, DT);
+
+    // The acceleration is held for seconds at a time, so the alpha-beta-gamma filter can
+    // track it with a small tracking index
+    let mut alpha_beta_gamma_filt = AlphaBetaGammaFilter::from_tracking_index(0.001, DT);
+
+    println!(
+        "Alpha-beta gains {:.3?}, alpha-beta-gamma gains {:.3?}",
+        alpha_beta_filt.get_gains(),
+        alpha_beta_gamma_filt.get_gains()
+    );
+
+    let mut position_sensor = PositionSensor::from_seed(1).with_trajectory(trajectory);
+
+    // Run simulation; every filter sees the same measurements
+    for _ in 0..num_data_pts {
+        let reading = position_sensor.measure();
+        let data_pt = reading.measurement;
+        klmn_filt.update(DVector::from_element(1, data_pt));
+        alpha_beta_filt.update(data_pt);
+        alpha_beta_gamma_filt.update(data_pt);
+
+        // Log data for plotting
+        true_vels.push(reading.truth.vel[0]);
+        klmn_vels.push(klmn_filt.get_state_estimate()[1]);
+        alpha_beta_vels.push(alpha_beta_filt.get_velocity());
+        alpha_beta_gamma_vels.push(alpha_beta_gamma_filt.get_velocity());
+    }
+
+    let rms_err = |estimates: &[f64]| -> f64 {
+        (estimates
+            .iter()
+            .zip(true_vels.iter())
+            .map(|(est, truth)| (est - truth).powi(2))
+            .sum::<f64>()
+            / num_data_pts as f64)
+            .sqrt()
+    };
+    println!(
+        "RMS velocity error [m/s]: Kalman filter {:.2}, alpha-beta {:.2}, alpha-beta-gamma {:.2}",
+        rms_err(&klmn_vels),
+        rms_err(&alpha_beta_vels),
+        rms_err(&alpha_beta_gamma_vels)
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let comparisons = [
+        ("a", "Alpha-Beta Filter", &alpha_beta_vels),
+        ("b", "Alpha-Beta-Gamma Filter", &alpha_beta_gamma_vels),
+    ];
+
+    for (plot_suffix, filter_name, vel_estimates) in comparisons {
+        let plot_labels = PlotLabels {
+            plot_pathname: format!("./plots/22{}_AlphaBetaFilter_VelFromPos.png", plot_suffix),
+            title: filter_name.to_string(),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: "Velocity [m/s]".to_string(),
+            y_axis_data1_label: "Kalman Filter".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(plot_labels, &times_s, &klmn_vels, vel_estimates);
+    }
+}
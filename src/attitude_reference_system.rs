@@ -0,0 +1,405 @@
+#![allow(non_snake_case)]
+
+use crate::kalman_filter::{KalmanFilter, SystemModel};
+use crate::nonlinear_kalman_filter::{
+    ExtendedKalmanFilter, NonlinearSystemModel, UnscentedKalmanFilter,
+};
+use nalgebra::{DMatrix, DVector, Matrix3, Quaternion, UnitQuaternion, Vector3};
+
+/// Gravitational acceleration used in the textbook examples [m/s^2]
+pub const GRAVITY: f64 = 9.8;
+
+/// Roll and pitch from the accelerometer alone, Chapter 11, pg. 110
+/// Only valid when the sensor is not accelerating, so the accelerometer measures gravity.
+/// accel: specific force along the body x, y and z axes [m/s^2]
+/// Returns [roll (phi), pitch (theta)] in radians; yaw is not observable from gravity
+pub fn euler_from_accel(accel: &Vector3<f64>) -> [f64; 2] {
+    let theta = (accel.x / GRAVITY).clamp(-1.0, 1.0).asin();
+    let phi = (-accel.y / (GRAVITY * theta.cos())).clamp(-1.0, 1.0).asin();
+
+    [phi, theta]
+}
+
+/// Quaternion [q0 q1 q2 q3] (scalar first) from roll, pitch and yaw in radians
+pub fn euler_to_quaternion(phi: f64, theta: f64, psi: f64) -> DVector<f64> {
+    let q = UnitQuaternion::from_euler_angles(phi, theta, psi);
+    DVector::from_column_slice(&[q.w, q.i, q.j, q.k])
+}
+
+/// Roll, pitch and yaw in radians from a quaternion [q0 q1 q2 q3] (scalar first).
+/// The quaternion is normalized first; the linear Kalman filter does not preserve unit norm.
+pub fn quaternion_to_euler(q: &DVector<f64>) -> Vector3<f64> {
+    let unit_q = UnitQuaternion::from_quaternion(Quaternion::new(q[0], q[1], q[2], q[3]));
+    let (phi, theta, psi) = unit_q.euler_angles();
+
+    Vector3::new(phi, theta, psi)
+}
+
+/// Discrete quaternion kinematics for body rates p, q, r [rad/s], Chapter 11, pg. 112
+/// x_k+1 = (I + dt/2 * Omega) * x_k
+pub fn quaternion_state_transition(gyro: &Vector3<f64>, dt: f64) -> DMatrix<f64> {
+    let (p, q, r) = (gyro.x, gyro.y, gyro.z);
+    let omega = DMatrix::from_row_slice(
+        4,
+        4,
+        &[
+            0.0, -p, -q, -r, //
+            p, 0.0, r, -q, //
+            q, -r, 0.0, p, //
+            r, q, -p, 0.0,
+        ],
+    );
+
+    DMatrix::identity(4, 4) + omega * (0.5 * dt)
+}
+
+/// Euler angle kinematics for body rates p, q, r [rad/s], Chapter 12, pg. 121
+/// x = [phi theta psi], u = [p q r]
+fn euler_state_transition(x: &DVector<f64>, u: &DVector<f64>, dt: f64) -> DVector<f64> {
+    let (sin_phi, cos_phi) = x[0].sin_cos();
+    let (tan_theta, sec_theta) = (x[1].tan(), 1.0 / x[1].cos());
+    let (p, q, r) = (u[0], u[1], u[2]);
+
+    let x_dot = DVector::from_column_slice(&[
+        p + q * sin_phi * tan_theta + r * cos_phi * tan_theta,
+        q * cos_phi - r * sin_phi,
+        q * sin_phi * sec_theta + r * cos_phi * sec_theta,
+    ]);
+
+    x + x_dot * dt
+}
+
+/// Jacobian of euler_state_transition with respect to x, Chapter 12, pg. 123
+fn euler_state_transition_jacobian(x: &DVector<f64>, u: &DVector<f64>, dt: f64) -> DMatrix<f64> {
+    let (sin_phi, cos_phi) = x[0].sin_cos();
+    let (tan_theta, sec_theta) = (x[1].tan(), 1.0 / x[1].cos());
+    let (q, r) = (u[1], u[2]);
+
+    let A = DMatrix::from_row_slice(
+        3,
+        3,
+        &[
+            q * cos_phi * tan_theta - r * sin_phi * tan_theta,
+            q * sin_phi * sec_theta.powi(2) + r * cos_phi * sec_theta.powi(2),
+            0.0,
+            -q * sin_phi - r * cos_phi,
+            0.0,
+            0.0,
+            q * cos_phi * sec_theta - r * sin_phi * sec_theta,
+            q * sin_phi * sec_theta * tan_theta + r * cos_phi * sec_theta * tan_theta,
+            0.0,
+        ],
+    );
+
+    DMatrix::identity(3, 3) + A * dt
+}
+
+/// Accelerometer roll and pitch are measured directly, H = [1 0 0; 0 1 0]
+fn euler_measurement(x: &DVector<f64>) -> DVector<f64> {
+    x.rows(0, 2).into_owned()
+}
+
+fn euler_measurement_jacobian(_x: &DVector<f64>) -> DMatrix<f64> {
+    DMatrix::from_row_slice(2, 3, &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+}
+
+fn euler_system_model(dt: f64, Q: DMatrix<f64>, R: DMatrix<f64>) -> NonlinearSystemModel {
+    NonlinearSystemModel::new(
+        Box::new(move |x, u| euler_state_transition(x, u, dt)),
+        Q,
+        Box::new(euler_measurement),
+        R,
+    )
+}
+
+/// Attitude from gyro and accelerometer with a linear Kalman filter on the quaternion,
+/// Chapter 11. The gyro rates drive the state transition and the accelerometer roll and
+/// pitch, converted to a quaternion, are the measurement.
+pub struct AttitudeKalmanFilter {
+    klmn_filt: KalmanFilter,
+    dt: f64,
+}
+
+impl AttitudeKalmanFilter {
+    /// Uses the textbook tuning, Q = 0.0001 * I, R = 10 * I, starting level, P = I
+    pub fn new(dt: f64) -> Self {
+        let system_model = SystemModel::new(
+            DMatrix::identity(4, 4),
+            DMatrix::identity(4, 4) * 0.0001,
+            DMatrix::identity(4, 4),
+            DMatrix::identity(4, 4) * 10.0,
+        );
+
+        Self {
+            klmn_filt: KalmanFilter::new(
+                system_model,
+                DVector::from_column_slice(&[1.0, 0.0, 0.0, 0.0]),
+                DMatrix::identity(4, 4),
+            ),
+            dt,
+        }
+    }
+
+    /// gyro: body rates [rad/s], accel: specific force [m/s^2]
+    pub fn update(&mut self, gyro: &Vector3<f64>, accel: &Vector3<f64>) {
+        let [phi, theta] = euler_from_accel(accel);
+
+        self.klmn_filt
+            .set_state_transition(quaternion_state_transition(gyro, self.dt));
+        self.klmn_filt.update(euler_to_quaternion(phi, theta, 0.0));
+    }
+
+    /// [roll, pitch, yaw] in radians
+    pub fn get_euler_angles(&self) -> Vector3<f64> {
+        quaternion_to_euler(&self.klmn_filt.get_state_estimate())
+    }
+
+    pub fn get_quaternion(&self) -> DVector<f64> {
+        self.klmn_filt.get_state_estimate()
+    }
+}
+
+/// Attitude from gyro and accelerometer with an extended Kalman filter on the Euler angles,
+/// Chapter 12
+pub struct AttitudeExtendedKalmanFilter {
+    ekf: ExtendedKalmanFilter,
+}
+
+impl AttitudeExtendedKalmanFilter {
+    /// Uses the textbook tuning, Q = diag(0.0001, 0.0001, 0.1), R = 10 * I, P = 10 * I
+    pub fn new(dt: f64) -> Self {
+        let system_model = euler_system_model(
+            dt,
+            DMatrix::from_diagonal(&DVector::from_column_slice(&[0.0001, 0.0001, 0.1])),
+            DMatrix::identity(2, 2) * 10.0,
+        );
+
+        Self {
+            ekf: ExtendedKalmanFilter::new(
+                system_model,
+                Box::new(move |x, u| euler_state_transition_jacobian(x, u, dt)),
+                Box::new(euler_measurement_jacobian),
+                DVector::zeros(3),
+                DMatrix::identity(3, 3) * 10.0,
+            ),
+        }
+    }
+
+    /// gyro: body rates [rad/s], accel: specific force [m/s^2]
+    pub fn update(&mut self, gyro: &Vector3<f64>, accel: &Vector3<f64>) {
+        let [phi, theta] = euler_from_accel(accel);
+
+        self.ekf.update_with_input(
+            &DVector::from_column_slice(gyro.as_slice()),
+            DVector::from_column_slice(&[phi, theta]),
+        );
+    }
+
+    /// [roll, pitch, yaw] in radians
+    pub fn get_euler_angles(&self) -> Vector3<f64> {
+        let x = self.ekf.get_state_estimate();
+        Vector3::new(x[0], x[1], x[2])
+    }
+}
+
+/// Attitude from gyro and accelerometer with an unscented Kalman filter on the Euler angles,
+/// Chapter 15
+pub struct AttitudeUnscentedKalmanFilter {
+    ukf: UnscentedKalmanFilter,
+}
+
+impl AttitudeUnscentedKalmanFilter {
+    /// Uses the textbook tuning, Q = diag(0.0001, 0.0001, 1), R = 10 * I, P = I, kappa = 0
+    pub fn new(dt: f64) -> Self {
+        let system_model = euler_system_model(
+            dt,
+            DMatrix::from_diagonal(&DVector::from_column_slice(&[0.0001, 0.0001, 1.0])),
+            DMatrix::identity(2, 2) * 10.0,
+        );
+
+        Self {
+            ukf: UnscentedKalmanFilter::new(
+                system_model,
+                0.0,
+                DVector::zeros(3),
+                DMatrix::identity(3, 3),
+            ),
+        }
+    }
+
+    /// gyro: body rates [rad/s], accel: specific force [m/s^2]
+    pub fn update(&mut self, gyro: &Vector3<f64>, accel: &Vector3<f64>) {
+        let [phi, theta] = euler_from_accel(accel);
+
+        self.ukf.update_with_input(
+            &DVector::from_column_slice(gyro.as_slice()),
+            DVector::from_column_slice(&[phi, theta]),
+        );
+    }
+
+    /// [roll, pitch, yaw] in radians
+    pub fn get_euler_angles(&self) -> Vector3<f64> {
+        let x = self.ukf.get_state_estimate();
+        Vector3::new(x[0], x[1], x[2])
+    }
+}
+
+/// Copy a 3 x 3 block into a DMatrix at (row, col)
+fn set_block3(mat: &mut DMatrix<f64>, row: usize, col: usize, block: &Matrix3<f64>) {
+    mat.view_mut((row, col), (3, 3)).copy_from(block);
+}
+
+/// Attitude and gyro bias from gyro and accelerometer with a multiplicative, error-state
+/// extended Kalman filter.
+///
+/// The nominal state is a unit quaternion and a gyro bias, propagated with the bias-corrected
+/// gyro rates. The filter itself estimates the 6 element error state
+/// [attitude error (3), gyro bias error (3)], so the covariance stays 6 x 6 and non-singular.
+/// The attitude error is a small rotation in the body frame, q_true = q_nominal * dq.
+/// After each correction the error is folded back into the nominal state and reset to zero.
+pub struct AttitudeErrorStateKalmanFilter {
+    // Nominal state
+    nom_q: UnitQuaternion<f64>,
+    nom_gyro_bias: Vector3<f64>,
+    // Error state covariance, 6 x 6
+    err_cvr_P: DMatrix<f64>,
+    // Noise
+    prcs_cvr_Q: DMatrix<f64>,
+    meas_cvr_R: DMatrix<f64>,
+    dt: f64,
+}
+
+impl AttitudeErrorStateKalmanFilter {
+    /// gyro_noise_std: gyro white noise, standard deviation per sample [rad/s]
+    /// gyro_bias_walk_std: gyro bias random walk, standard deviation per sample [rad/s]
+    /// accel_noise_std: accelerometer white noise, standard deviation [m/s^2]
+    /// Starts level with zero bias, 1 rad attitude and 0.1 rad/s bias uncertainty (1 sigma)
+    pub fn new(
+        dt: f64,
+        gyro_noise_std: f64,
+        gyro_bias_walk_std: f64,
+        accel_noise_std: f64,
+    ) -> Self {
+        let mut prcs_cvr_Q = DMatrix::zeros(6, 6);
+        set_block3(
+            &mut prcs_cvr_Q,
+            0,
+            0,
+            &(Matrix3::identity() * (gyro_noise_std * dt).powi(2)),
+        );
+        set_block3(
+            &mut prcs_cvr_Q,
+            3,
+            3,
+            &(Matrix3::identity() * gyro_bias_walk_std.powi(2)),
+        );
+
+        Self {
+            nom_q: UnitQuaternion::identity(),
+            nom_gyro_bias: Vector3::zeros(),
+            err_cvr_P: DMatrix::from_diagonal(&DVector::from_column_slice(&[
+                1.0, 1.0, 1.0, 0.01, 0.01, 0.01,
+            ])),
+            prcs_cvr_Q,
+            meas_cvr_R: DMatrix::identity(3, 3) * accel_noise_std.powi(2),
+            dt,
+        }
+    }
+
+    /// Propagate the nominal state with one gyro sample and grow the error covariance.
+    /// gyro: body rates [rad/s]
+    pub fn predict(&mut self, gyro: &Vector3<f64>) {
+        let delta_angle = (gyro - self.nom_gyro_bias) * self.dt;
+        let delta_q = UnitQuaternion::from_scaled_axis(delta_angle);
+
+        // Nominal state; the bias is modeled as constant
+        self.nom_q *= delta_q;
+
+        // Error state transition
+        // d(attitude error) = R(delta_angle)' * attitude error - dt * gyro bias error
+        let mut err_trns_A = DMatrix::identity(6, 6);
+        set_block3(
+            &mut err_trns_A,
+            0,
+            0,
+            &delta_q.to_rotation_matrix().matrix().transpose(),
+        );
+        set_block3(&mut err_trns_A, 0, 3, &(-Matrix3::identity() * self.dt));
+
+        self.err_cvr_P =
+            KalmanFilter::predict_error_covariance(&self.err_cvr_P, &err_trns_A, &self.prcs_cvr_Q);
+    }
+
+    /// Correct the attitude and gyro bias with the gravity direction measured by the
+    /// accelerometer, then fold the error state into the nominal state.
+    /// accel: specific force [m/s^2]
+    pub fn correct(&mut self, accel: &Vector3<f64>) {
+        // Predicted specific force of a stationary sensor, gravity is along +z in the
+        // navigation frame so the accelerometer reads -g
+        let prd_z = self.nom_q.inverse() * Vector3::new(0.0, 0.0, -GRAVITY);
+
+        // Measurement Jacobian with respect to the error state, [ [prd_z]x 0 ]
+        let mut err_to_meas_H = DMatrix::zeros(3, 6);
+        err_to_meas_H
+            .view_mut((0, 0), (3, 3))
+            .copy_from(&prd_z.cross_matrix());
+
+        let klmn_gain_K =
+            KalmanFilter::calculate_kalman_gain(&self.err_cvr_P, &err_to_meas_H, &self.meas_cvr_R);
+
+        let residual = DVector::from_column_slice((accel - prd_z).as_slice());
+        let err_x = &klmn_gain_K * residual;
+
+        // Joseph form keeps the covariance symmetric and positive definite
+        let I_KH = DMatrix::identity(6, 6) - &klmn_gain_K * &err_to_meas_H;
+        self.err_cvr_P = &I_KH * &self.err_cvr_P * I_KH.transpose()
+            + &klmn_gain_K * &self.meas_cvr_R * klmn_gain_K.transpose();
+
+        self.reset(&err_x);
+    }
+
+    // Fold the estimated error into the nominal state and return the error to zero
+    fn reset(&mut self, err_x: &DVector<f64>) {
+        let err_angle = Vector3::new(err_x[0], err_x[1], err_x[2]);
+        let err_gyro_bias = Vector3::new(err_x[3], err_x[4], err_x[5]);
+
+        self.nom_q *= UnitQuaternion::from_scaled_axis(err_angle);
+        self.nom_gyro_bias += err_gyro_bias;
+
+        // The attitude error is now expressed about the corrected attitude
+        let mut reset_G = DMatrix::identity(6, 6);
+        set_block3(
+            &mut reset_G,
+            0,
+            0,
+            &(Matrix3::identity() - (err_angle * 0.5).cross_matrix()),
+        );
+        self.err_cvr_P = &reset_G * &self.err_cvr_P * reset_G.transpose();
+    }
+
+    /// gyro: body rates [rad/s], accel: specific force [m/s^2]
+    pub fn update(&mut self, gyro: &Vector3<f64>, accel: &Vector3<f64>) {
+        self.predict(gyro);
+        self.correct(accel);
+    }
+
+    /// [roll, pitch, yaw] in radians
+    pub fn get_euler_angles(&self) -> Vector3<f64> {
+        let (phi, theta, psi) = self.nom_q.euler_angles();
+        Vector3::new(phi, theta, psi)
+    }
+
+    pub fn get_quaternion(&self) -> UnitQuaternion<f64> {
+        self.nom_q
+    }
+
+    /// Estimated gyro bias [rad/s]
+    pub fn get_gyro_bias(&self) -> Vector3<f64> {
+        self.nom_gyro_bias
+    }
+
+    /// Error state covariance, [attitude error, gyro bias error]
+    pub fn get_error_covariance(&self) -> DMatrix<f64> {
+        self.err_cvr_P.clone()
+    }
+}
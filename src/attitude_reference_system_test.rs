@@ -0,0 +1,346 @@
+use crate::sensor_spoofs::{ImuSample, ImuSensor, ImuTruth, InertialSensorErrors, SensorReading};
+use crate::trajectory::{Segment, Trajectory};
+use crate::utils::{plot_two_lines, read_mat_file_arrays, PlotLabels};
+use kalman_filter_for_beginners_rust::attitude_reference_system::{
+    euler_from_accel, AttitudeErrorStateKalmanFilter, AttitudeExtendedKalmanFilter,
+    AttitudeKalmanFilter, AttitudeUnscentedKalmanFilter,
+};
+use kalman_filter_for_beginners_rust::recursive_filters::{
+    ComplementaryFilter1stOrder, ComplementaryFilter2ndOrder,
+};
+use nalgebra::{Matrix3, Vector3};
+
+// Attitude reference system inputs based on textbook example
+const ARS_DT: f64 = 0.01;
+
+/// (gyro samples, accelerometer samples)
+type ArsData = (Vec<Vector3<f64>>, Vec<Vector3<f64>>);
+
+/// Load the textbook gyro [rad/s] and accelerometer [m/s^2] recordings.
+/// These aren't bundled with the repo; copy ArsGyro.mat and ArsAccel.mat from the textbook
+/// data files into ./data to use them. Without them a simulated IMU stands in, see
+/// simulate_ars_data. Returns the data and whether it was simulated.
+pub fn load_ars_data() -> (ArsData, bool) {
+    let gyro_arrs = read_mat_file_arrays("./data/ArsGyro.mat", &["wx", "wy", "wz"]);
+    let accel_arrs = read_mat_file_arrays("./data/ArsAccel.mat", &["fx", "fy", "fz"]);
+
+    let to_vector3 = |arrs: &Vec<Vec<f64>>| -> Vec<Vector3<f64>> {
+        (0..arrs[0].len())
+            .map(|i| Vector3::new(arrs[0][i], arrs[1][i], arrs[2][i]))
+            .collect()
+    };
+
+    match (gyro_arrs, accel_arrs) {
+        (Some(gyro_arrs), Some(accel_arrs)) => {
+            ((to_vector3(&gyro_arrs), to_vector3(&accel_arrs)), false)
+        }
+        _ => {
+            println!("./data/ArsGyro.mat and ./data/ArsAccel.mat not found, using a simulated IMU");
+            (simulate_ars_data(), true)
+        }
+    }
+}
+
+/// Stand in for the textbook recording, about as long and at the same sample rate: rocking
+/// in roll and pitch about level, with a constant gyro bias for the filters to estimate
+fn simulate_ars_data() -> ArsData {
+    const DURATION_S: f64 = 40.0;
+    let num_data_pts: usize = (DURATION_S / ARS_DT) as usize;
+
+    // Euler angles [roll, pitch, yaw], rocking at 0.05 Hz; the sinusoid offset runs from 0 to
+    // twice the amplitude, so starting at -amplitude swings about level
+    let attitude = Trajectory::new(&[-0.5, -0.25, 0.0], &[0.0, 0.0, 0.0]).then(Segment::Sinusoid {
+        duration_s: DURATION_S,
+        amplitude: vec![0.5, 0.25, 0.0],
+        freq_hz: 0.05,
+    });
+    let gyro_errors =
+        InertialSensorErrors::new(0.005).with_bias(Vector3::new(0.01, -0.02, 0.005), 0.0);
+
+    let mut imu = ImuSensor::from_seed(2, ARS_DT)
+        .with_attitude(attitude)
+        .with_gyro_errors(gyro_errors)
+        .with_accel_errors(InertialSensorErrors::new(0.1));
+
+    (0..num_data_pts)
+        .map(|_| {
+            let sample = imu.measure().measurement;
+            (sample.gyro, sample.accel)
+        })
+        .unzip()
+}
+
+/// Roll and pitch rates from body rates p, q, r at the given roll and pitch, Chapter 12
+fn euler_rates(gyro: &Vector3<f64>, phi: f64, theta: f64) -> [f64; 2] {
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    [
+        gyro.x + gyro.y * sin_phi * theta.tan() + gyro.z * cos_phi * theta.tan(),
+        gyro.y * cos_phi - gyro.z * sin_phi,
+    ]
+}
+
+/// Run one attitude filter over the textbook recording and plot its roll and pitch against
+/// the accelerometer-only angles. filter_update takes gyro and accelerometer samples and
+/// returns the new [roll, pitch, yaw] estimate in radians.
+fn attitude_example(
+    filter_name: &str,
+    plot_pathname_prefix: &str,
+    mut filter_update: impl FnMut(&Vector3<f64>, &Vector3<f64>) -> Vector3<f64>,
+) {
+    let ((gyro_data, accel_data), is_simulated) = load_ars_data();
+
+    // Setup simulation & data logging
+    let num_data_pts: usize = gyro_data.len().min(accel_data.len());
+
+    let times_s: Vec<f64> = (0..num_data_pts).map(|i| i as f64 * ARS_DT).collect();
+
+    let mut accel_rolls = Vec::<f64>::with_capacity(num_data_pts);
+    let mut accel_pitches = Vec::<f64>::with_capacity(num_data_pts);
+    let mut roll_estimates = Vec::<f64>::with_capacity(num_data_pts);
+    let mut pitch_estimates = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Run simulation
+    for (gyro, accel) in gyro_data.iter().zip(accel_data.iter()) {
+        let euler_est = filter_update(gyro, accel);
+        let [accel_roll, accel_pitch] = euler_from_accel(accel);
+
+        // Log data for plotting, in degrees
+        accel_rolls.push(accel_roll.to_degrees());
+        accel_pitches.push(accel_pitch.to_degrees());
+        roll_estimates.push(euler_est[0].to_degrees());
+        pitch_estimates.push(euler_est[1].to_degrees());
+    }
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let angles = [
+        ("a", "Roll angle [deg]", &accel_rolls, &roll_estimates),
+        ("b", "Pitch angle [deg]", &accel_pitches, &pitch_estimates),
+    ];
+
+    for (plot_suffix, y_axis_label, measured, estimates) in angles {
+        let plot_labels = PlotLabels {
+            plot_pathname: format!(
+                "./plots/{}{}_{}_Attitude.png",
+                plot_pathname_prefix,
+                plot_suffix,
+                filter_name.replace([' ', '-'], "")
+            ),
+            title: if is_simulated {
+                format!("{}, Simulated IMU", filter_name)
+            } else {
+                filter_name.to_string()
+            },
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: y_axis_label.to_string(),
+            y_axis_data1_label: "Accelerometer".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(plot_labels, &times_s, measured, estimates);
+    }
+}
+
+pub fn attitude_kalman_filter_example() {
+    let mut att_filt = AttitudeKalmanFilter::new(ARS_DT);
+
+    attitude_example("Kalman Filter", "09", |gyro, accel| {
+        att_filt.update(gyro, accel);
+        att_filt.get_euler_angles()
+    });
+}
+
+pub fn attitude_extended_kalman_filter_example() {
+    let mut att_filt = AttitudeExtendedKalmanFilter::new(ARS_DT);
+
+    attitude_example("Extended Kalman Filter", "10", |gyro, accel| {
+        att_filt.update(gyro, accel);
+        att_filt.get_euler_angles()
+    });
+}
+
+pub fn attitude_unscented_kalman_filter_example() {
+    let mut att_filt = AttitudeUnscentedKalmanFilter::new(ARS_DT);
+
+    attitude_example("Unscented Kalman Filter", "11", |gyro, accel| {
+        att_filt.update(gyro, accel);
+        att_filt.get_euler_angles()
+    });
+}
+
+pub fn attitude_error_state_kalman_filter_example() {
+    // Noise levels chosen for the textbook recording; gyro [rad/s], accelerometer [m/s^2]
+    let mut att_filt = AttitudeErrorStateKalmanFilter::new(ARS_DT, 0.01, 0.0001, 1.0);
+
+    attitude_example("Error-State Kalman Filter", "12", |gyro, accel| {
+        att_filt.update(gyro, accel);
+        att_filt.get_euler_angles()
+    });
+
+    println!(
+        "Error-State Kalman Filter gyro bias estimate [rad/s]: {:.5?}",
+        att_filt.get_gyro_bias().as_slice()
+    );
+}
+
+pub fn attitude_complementary_filter_1st_order_example() {
+    // Crossover at 0.1 Hz; gyro above, accelerometer below
+    let mut roll_filt = ComplementaryFilter1stOrder::from_crossover_frequency(0.1, ARS_DT);
+    let mut pitch_filt = ComplementaryFilter1stOrder::from_crossover_frequency(0.1, ARS_DT);
+
+    attitude_example("1st Order Complementary Filter", "13", |gyro, accel| {
+        let [roll_rate, pitch_rate] =
+            euler_rates(gyro, roll_filt.get_estimate(), pitch_filt.get_estimate());
+        let [accel_roll, accel_pitch] = euler_from_accel(accel);
+
+        roll_filt.update(roll_rate, accel_roll);
+        pitch_filt.update(pitch_rate, accel_pitch);
+
+        Vector3::new(roll_filt.get_estimate(), pitch_filt.get_estimate(), 0.0)
+    });
+}
+
+pub fn attitude_complementary_filter_2nd_order_example() {
+    // Crossover at 0.1 Hz; gyro above, accelerometer below
+    let mut roll_filt = ComplementaryFilter2ndOrder::from_crossover_frequency(0.1, ARS_DT);
+    let mut pitch_filt = ComplementaryFilter2ndOrder::from_crossover_frequency(0.1, ARS_DT);
+
+    attitude_example("2nd Order Complementary Filter", "14", |gyro, accel| {
+        let [roll_rate, pitch_rate] =
+            euler_rates(gyro, roll_filt.get_estimate(), pitch_filt.get_estimate());
+        let [accel_roll, accel_pitch] = euler_from_accel(accel);
+
+        roll_filt.update(roll_rate, accel_roll);
+        pitch_filt.update(pitch_rate, accel_pitch);
+
+        Vector3::new(roll_filt.get_estimate(), pitch_filt.get_estimate(), 0.0)
+    });
+
+    println!(
+        "2nd Order Complementary Filter rate bias estimate [rad/s]: roll {:.5}, pitch {:.5}",
+        roll_filt.get_rate_bias(),
+        pitch_filt.get_rate_bias()
+    );
+}
+
+/// [roll, pitch] estimates in degrees from one attitude filter over simulated IMU samples,
+/// filter_update as in attitude_example
+fn simulated_imu_estimates(
+    readings: &[SensorReading<ImuSample, ImuTruth>],
+    mut filter_update: impl FnMut(&Vector3<f64>, &Vector3<f64>) -> Vector3<f64>,
+) -> Vec<[f64; 2]> {
+    readings
+        .iter()
+        .map(|reading| {
+            let euler_est = filter_update(&reading.measurement.gyro, &reading.measurement.accel);
+            [euler_est[0].to_degrees(), euler_est[1].to_degrees()]
+        })
+        .collect()
+}
+
+/// Run the attitude filters over a simulated IMU, rocking in roll and pitch while turning
+/// slowly, so their errors can be measured against the true attitude instead of the
+/// accelerometer. The gyro has a bias, scale factor and misalignment errors.
+pub fn attitude_simulated_imu_example() {
+    const DURATION_S: f64 = 30.0;
+    let num_data_pts: usize = (DURATION_S / ARS_DT) as usize;
+
+    // Euler angles [roll, pitch, yaw], rocking at 0.1 Hz and turning at 3 deg/s. The sinusoid
+    // offset runs from 0 to twice the amplitude, so starting at -amplitude swings about level.
+    let attitude =
+        Trajectory::new(&[-0.3, -0.15, 0.0], &[0.0, 0.0, 0.05]).then(Segment::Sinusoid {
+            duration_s: DURATION_S,
+            amplitude: vec![0.3, 0.15, 0.0],
+            freq_hz: 0.1,
+        });
+    let gyro_errors = InertialSensorErrors::new(0.005)
+        .with_bias(Vector3::new(0.01, -0.02, 0.005), 0.00001)
+        .with_scale_factors(Vector3::new(0.01, -0.005, 0.002))
+        .with_misalignment(Matrix3::new(
+            0.0, 0.002, -0.001, //
+            -0.001, 0.0, 0.003, //
+            0.002, 0.001, 0.0,
+        ));
+    let accel_errors =
+        InertialSensorErrors::new(0.1).with_bias(Vector3::new(0.05, -0.05, 0.0), 0.0);
+
+    let mut imu = ImuSensor::from_seed(1, ARS_DT)
+        .with_attitude(attitude)
+        .with_gyro_errors(gyro_errors)
+        .with_accel_errors(accel_errors);
+    let readings: Vec<_> = (0..num_data_pts).map(|_| imu.measure()).collect();
+
+    let times_s: Vec<f64> = readings
+        .iter()
+        .map(|reading| reading.truth.time_s)
+        .collect();
+    let true_rolls: Vec<f64> = readings
+        .iter()
+        .map(|reading| reading.truth.euler_angles[0].to_degrees())
+        .collect();
+
+    let rms_error_deg = |estimates: &[[f64; 2]], axis: usize| -> f64 {
+        let sum_sq: f64 = estimates
+            .iter()
+            .zip(readings.iter())
+            .map(|(est, reading)| {
+                (est[axis] - reading.truth.euler_angles[axis].to_degrees()).powi(2)
+            })
+            .sum();
+        (sum_sq / estimates.len() as f64).sqrt()
+    };
+
+    let mut klmn_filt = AttitudeKalmanFilter::new(ARS_DT);
+    let mut ekf = AttitudeExtendedKalmanFilter::new(ARS_DT);
+    let mut ukf = AttitudeUnscentedKalmanFilter::new(ARS_DT);
+    let mut eskf = AttitudeErrorStateKalmanFilter::new(ARS_DT, 0.005, 0.00001, 0.1);
+
+    let klmn_estimates = simulated_imu_estimates(&readings, |gyro, accel| {
+        klmn_filt.update(gyro, accel);
+        klmn_filt.get_euler_angles()
+    });
+    let ekf_estimates = simulated_imu_estimates(&readings, |gyro, accel| {
+        ekf.update(gyro, accel);
+        ekf.get_euler_angles()
+    });
+    let ukf_estimates = simulated_imu_estimates(&readings, |gyro, accel| {
+        ukf.update(gyro, accel);
+        ukf.get_euler_angles()
+    });
+    let eskf_estimates = simulated_imu_estimates(&readings, |gyro, accel| {
+        eskf.update(gyro, accel);
+        eskf.get_euler_angles()
+    });
+
+    let results = [
+        ("Kalman Filter", &klmn_estimates),
+        ("Extended Kalman Filter", &ekf_estimates),
+        ("Unscented Kalman Filter", &ukf_estimates),
+        ("Error-State Kalman Filter", &eskf_estimates),
+    ];
+    for (filter_name, estimates) in results {
+        println!(
+            "Simulated IMU, {} RMS error [deg]: roll {:.2}, pitch {:.2}",
+            filter_name,
+            rms_error_deg(estimates, 0),
+            rms_error_deg(estimates, 1)
+        );
+    }
+    println!(
+        "Simulated IMU gyro bias [rad/s]: true {:.5?}, Error-State Kalman Filter estimate {:.5?}",
+        readings.last().unwrap().truth.gyro_bias.as_slice(),
+        eskf.get_gyro_bias().as_slice()
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let eskf_rolls: Vec<f64> = eskf_estimates.iter().map(|est| est[0]).collect();
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/28_Attitude_SimulatedImu.png".to_string(),
+        title: "Error-State Kalman Filter, Simulated IMU".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Roll angle [deg]".to_string(),
+        y_axis_data1_label: "Truth".to_string(),
+        y_axis_data2_label: "Error-State Kalman Filter".to_string(),
+    };
+
+    plot_two_lines(plot_labels, &times_s, &true_rolls, &eskf_rolls);
+}
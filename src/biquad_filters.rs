@@ -0,0 +1,294 @@
+use crate::recursive_filters::Filter;
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+/// Second order IIR filter section, direct form II transposed
+/// H(z) = (b0 + b1 z^-1 + b2 z^-2) / (1 + a1 z^-1 + a2 z^-2)
+///
+/// y_k = b0 * x_k + s1
+/// s1 = b1 * x_k - a1 * y_k + s2
+/// s2 = b2 * x_k - a2 * y_k
+///
+/// The design helpers use the bilinear transform with the cutoff frequency prewarped, so the
+/// -3 dB point lands exactly on the requested frequency.
+/// Like LowPassFilter1stOrder, the filter starts from the first data value, i.e. its state is
+/// initialized as if that value had been constant forever.
+#[derive(Clone)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2], // a1, a2; a0 normalized to 1
+    state: [f64; 2],
+    output: f64,
+    initialized: bool,
+}
+
+impl Biquad {
+    /// b: numerator coefficients [b0, b1, b2]
+    /// a: denominator coefficients [a0, a1, a2], a0 != 0; all are divided by a0
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        assert!(
+            a[0] != 0.0,
+            "Biquad: Leading denominator coefficient, a0, must not be zero."
+        );
+
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: [0.0; 2],
+            output: 0.0,
+            initialized: false,
+        }
+    }
+
+    /// 2nd order Butterworth low pass, maximally flat pass band
+    /// cutoff_freq_hz: -3 dB frequency, 0 < cutoff_freq_hz < 0.5 / dt
+    /// dt: sample period [s]
+    pub fn butterworth_low_pass(cutoff_freq_hz: f64, dt: f64) -> Self {
+        Self::low_pass(cutoff_freq_hz, FRAC_1_SQRT_2, dt)
+    }
+
+    /// 2nd order Butterworth high pass, maximally flat pass band
+    /// cutoff_freq_hz: -3 dB frequency, 0 < cutoff_freq_hz < 0.5 / dt
+    /// dt: sample period [s]
+    pub fn butterworth_high_pass(cutoff_freq_hz: f64, dt: f64) -> Self {
+        Self::high_pass(cutoff_freq_hz, FRAC_1_SQRT_2, dt)
+    }
+
+    /// Butterworth band pass, unity gain at the center frequency
+    /// low_cutoff_freq_hz, high_cutoff_freq_hz: -3 dB frequencies either side of the center
+    /// dt: sample period [s]
+    pub fn butterworth_band_pass(
+        low_cutoff_freq_hz: f64,
+        high_cutoff_freq_hz: f64,
+        dt: f64,
+    ) -> Self {
+        let (k, q) = band_center_and_q(low_cutoff_freq_hz, high_cutoff_freq_hz, dt);
+        let k2 = k * k;
+
+        Self::new(
+            [k / q, 0.0, -k / q],
+            [1.0 + k / q + k2, 2.0 * (k2 - 1.0), 1.0 - k / q + k2],
+        )
+    }
+
+    /// Butterworth notch (band stop), zero gain at the center frequency
+    /// low_cutoff_freq_hz, high_cutoff_freq_hz: -3 dB frequencies either side of the center
+    /// dt: sample period [s]
+    pub fn butterworth_notch(low_cutoff_freq_hz: f64, high_cutoff_freq_hz: f64, dt: f64) -> Self {
+        let (k, q) = band_center_and_q(low_cutoff_freq_hz, high_cutoff_freq_hz, dt);
+        let k2 = k * k;
+
+        Self::new(
+            [1.0 + k2, 2.0 * (k2 - 1.0), 1.0 + k2],
+            [1.0 + k / q + k2, 2.0 * (k2 - 1.0), 1.0 - k / q + k2],
+        )
+    }
+
+    /// 2nd order low pass with quality factor q; q = 1/sqrt(2) is Butterworth
+    fn low_pass(cutoff_freq_hz: f64, q: f64, dt: f64) -> Self {
+        let k = prewarp(cutoff_freq_hz, dt);
+        let k2 = k * k;
+
+        Self::new(
+            [k2, 2.0 * k2, k2],
+            [1.0 + k / q + k2, 2.0 * (k2 - 1.0), 1.0 - k / q + k2],
+        )
+    }
+
+    /// 2nd order high pass with quality factor q; q = 1/sqrt(2) is Butterworth
+    fn high_pass(cutoff_freq_hz: f64, q: f64, dt: f64) -> Self {
+        let k = prewarp(cutoff_freq_hz, dt);
+        let k2 = k * k;
+
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [1.0 + k / q + k2, 2.0 * (k2 - 1.0), 1.0 - k / q + k2],
+        )
+    }
+
+    /// 1st order low pass as a biquad with b2 = a2 = 0, for odd order cascades
+    fn low_pass_1st_order(cutoff_freq_hz: f64, dt: f64) -> Self {
+        let k = prewarp(cutoff_freq_hz, dt);
+
+        Self::new([k, k, 0.0], [1.0 + k, k - 1.0, 0.0])
+    }
+
+    /// 1st order high pass as a biquad with b2 = a2 = 0, for odd order cascades
+    fn high_pass_1st_order(cutoff_freq_hz: f64, dt: f64) -> Self {
+        let k = prewarp(cutoff_freq_hz, dt);
+
+        Self::new([1.0, -1.0, 0.0], [1.0 + k, k - 1.0, 0.0])
+    }
+
+    pub fn update(&mut self, data: f64) {
+        let [b0, b1, b2] = self.b;
+        let [a1, a2] = self.a;
+
+        if !(self.initialized) {
+            // Steady state for a constant input: y = H(1) * x
+            let dc_gain = (b0 + b1 + b2) / (1.0 + a1 + a2);
+            let output = dc_gain * data;
+            self.state[1] = b2 * data - a2 * output;
+            self.state[0] = b1 * data - a1 * output + self.state[1];
+            self.initialized = true;
+        }
+
+        self.output = b0 * data + self.state[0];
+        self.state[0] = b1 * data - a1 * self.output + self.state[1];
+        self.state[1] = b2 * data - a2 * self.output;
+    }
+
+    pub fn get_output(&self) -> f64 {
+        self.output
+    }
+
+    /// Normalized coefficients, ([b0, b1, b2], [1, a1, a2])
+    pub fn get_coefficients(&self) -> ([f64; 3], [f64; 3]) {
+        (self.b, [1.0, self.a[0], self.a[1]])
+    }
+}
+
+impl Filter for Biquad {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_output()
+    }
+
+    fn reset(&mut self) {
+        self.state = [0.0; 2];
+        self.output = 0.0;
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// Biquad sections in series, for filters of order higher than two.
+/// Each section's output is the next section's input.
+pub struct BiquadCascade {
+    sections: Vec<Biquad>,
+}
+
+impl BiquadCascade {
+    pub fn new(sections: Vec<Biquad>) -> Self {
+        assert!(
+            !sections.is_empty(),
+            "BiquadCascade: Number of sections must be greater than zero."
+        );
+
+        Self { sections }
+    }
+
+    /// Butterworth low pass of any order, as order / 2 biquads plus a 1st order section if
+    /// the order is odd
+    /// cutoff_freq_hz: -3 dB frequency, 0 < cutoff_freq_hz < 0.5 / dt
+    /// dt: sample period [s]
+    pub fn butterworth_low_pass(order: usize, cutoff_freq_hz: f64, dt: f64) -> Self {
+        let mut sections: Vec<Biquad> = butterworth_section_qs(order)
+            .into_iter()
+            .map(|q| Biquad::low_pass(cutoff_freq_hz, q, dt))
+            .collect();
+        if order % 2 == 1 {
+            sections.push(Biquad::low_pass_1st_order(cutoff_freq_hz, dt));
+        }
+
+        Self::new(sections)
+    }
+
+    /// Butterworth high pass of any order, as order / 2 biquads plus a 1st order section if
+    /// the order is odd
+    /// cutoff_freq_hz: -3 dB frequency, 0 < cutoff_freq_hz < 0.5 / dt
+    /// dt: sample period [s]
+    pub fn butterworth_high_pass(order: usize, cutoff_freq_hz: f64, dt: f64) -> Self {
+        let mut sections: Vec<Biquad> = butterworth_section_qs(order)
+            .into_iter()
+            .map(|q| Biquad::high_pass(cutoff_freq_hz, q, dt))
+            .collect();
+        if order % 2 == 1 {
+            sections.push(Biquad::high_pass_1st_order(cutoff_freq_hz, dt));
+        }
+
+        Self::new(sections)
+    }
+
+    pub fn update(&mut self, data: f64) {
+        let mut section_input = data;
+        for section in self.sections.iter_mut() {
+            section.update(section_input);
+            section_input = section.get_output();
+        }
+    }
+
+    pub fn get_output(&self) -> f64 {
+        self.sections.last().unwrap().get_output()
+    }
+
+    pub fn get_sections(&self) -> &[Biquad] {
+        &self.sections
+    }
+}
+
+impl Filter for BiquadCascade {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_output()
+    }
+
+    fn reset(&mut self) {
+        for section in self.sections.iter_mut() {
+            Filter::reset(section);
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.sections[0].is_initialized()
+    }
+}
+
+/// Bilinear transform prewarping, K = tan(pi * f * dt)
+fn prewarp(freq_hz: f64, dt: f64) -> f64 {
+    assert!(
+        (freq_hz > 0.0) && (dt > 0.0) && (freq_hz * dt < 0.5),
+        "Biquad: Frequency must be between zero and the Nyquist frequency, 0.5 / dt."
+    );
+
+    (PI * freq_hz * dt).tan()
+}
+
+/// Prewarped center frequency and quality factor of a band between two -3 dB frequencies
+fn band_center_and_q(low_cutoff_freq_hz: f64, high_cutoff_freq_hz: f64, dt: f64) -> (f64, f64) {
+    assert!(
+        low_cutoff_freq_hz < high_cutoff_freq_hz,
+        "Biquad: Low cutoff frequency must be less than the high cutoff frequency."
+    );
+
+    let k_low = prewarp(low_cutoff_freq_hz, dt);
+    let k_high = prewarp(high_cutoff_freq_hz, dt);
+    let k_center = (k_low * k_high).sqrt();
+
+    (k_center, k_center / (k_high - k_low))
+}
+
+/// Quality factors of the 2nd order sections of a Butterworth filter, from its pole pairs
+/// q_i = 1 / (2 cos(theta_i)), i = 1 .. order / 2
+/// theta_i = (order - 2i + 1) pi / (2 * order), angle of pole pair i from the negative real axis
+fn butterworth_section_qs(order: usize) -> Vec<f64> {
+    assert!(
+        order > 0,
+        "BiquadCascade: Filter order must be greater than zero."
+    );
+
+    (1..=order / 2)
+        .map(|i| {
+            let theta = (order - 2 * i + 1) as f64 * PI / (2 * order) as f64;
+            1.0 / (2.0 * theta.cos())
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+use nalgebra::Complex;
+use std::f64::consts::PI;
+
+/// Linear filter as a discrete transfer function, for analyzing how a filter shapes and
+/// delays a signal before choosing its window size or alpha.
+/// H(z) = (b0 + b1 z^-1 + ... + bM z^-M) / (a0 + a1 z^-1 + ... + aN z^-N)
+/// y_k = (b0 x_k + ... + bM x_k-M - a1 y_k-1 - ... - aN y_k-N) / a0
+///
+/// The responses describe the filter starting at rest, with all past data zero; the filter
+/// structs instead start from their first data point, which hides the initial transient.
+pub struct TransferFunction {
+    b: Vec<f64>,
+    a: Vec<f64>,
+}
+
+impl TransferFunction {
+    /// b: numerator (feed forward) coefficients
+    /// a: denominator (feedback) coefficients, a0 != 0
+    pub fn new(b: Vec<f64>, a: Vec<f64>) -> Self {
+        assert!(
+            !b.is_empty() && !a.is_empty() && a[0] != 0.0,
+            "TransferFunction: Coefficients must not be empty, and a0 must not be zero."
+        );
+
+        Self { b, a }
+    }
+
+    /// MovingAverageFilter, H(z) = (1 + z^-1 + ... + z^-(n-1)) / n
+    pub fn from_moving_average(window_size: usize) -> Self {
+        assert!(
+            window_size > 0,
+            "TransferFunction: Window size must be greater than zero."
+        );
+
+        Self::new(vec![1.0 / window_size as f64; window_size], vec![1.0])
+    }
+
+    /// LowPassFilter1stOrder, H(z) = (1 - alpha) / (1 - alpha z^-1)
+    pub fn from_low_pass_1st_order(alpha: f64) -> Self {
+        assert!(
+            (alpha > 0.0) && (alpha < 1.0),
+            "TransferFunction: Smoothing factor, alpha, \
+             must be between zero and one, 0.0 < alpha < 1.0"
+        );
+
+        Self::new(vec![1.0 - alpha], vec![1.0, -alpha])
+    }
+
+    /// Kalman filter for a scalar model once its gain has settled, see
+    /// steady_state_kalman_gain. From measurement z to state estimate x:
+    /// x_k = (1 - K h) a x_k-1 + K z_k,  H(z) = K / (1 - (1 - K h) a z^-1)
+    pub fn from_steady_state_kalman(a: f64, q: f64, h: f64, r: f64) -> Self {
+        let klmn_gain = steady_state_kalman_gain(a, q, h, r);
+
+        Self::new(vec![klmn_gain], vec![1.0, -(1.0 - klmn_gain * h) * a])
+    }
+
+    /// H(e^jw) at the given frequency, w = 2 pi freq_hz dt
+    /// dt: sample period [s]
+    pub fn frequency_response(&self, freq_hz: f64, dt: f64) -> Complex<f64> {
+        let omega = 2.0 * PI * freq_hz * dt;
+
+        polynomial_response(&self.b, omega) / polynomial_response(&self.a, omega)
+    }
+
+    /// Gain at the given frequency [dB]; -3 dB is half power
+    pub fn magnitude_db(&self, freq_hz: f64, dt: f64) -> f64 {
+        20.0 * self
+            .frequency_response(freq_hz, dt)
+            .norm_sqr()
+            .sqrt()
+            .log10()
+    }
+
+    /// Phase shift at the given frequency, wrapped to (-180, 180] [deg]
+    pub fn phase_deg(&self, freq_hz: f64, dt: f64) -> f64 {
+        let response = self.frequency_response(freq_hz, dt);
+        response.im.atan2(response.re).to_degrees()
+    }
+
+    /// Magnitude [dB] and phase [deg] at each frequency, for a Bode plot.
+    /// The phase is unwrapped, so it keeps falling past -180 deg instead of jumping.
+    pub fn bode(&self, freqs_hz: &[f64], dt: f64) -> (Vec<f64>, Vec<f64>) {
+        let magnitudes_db = freqs_hz.iter().map(|&f| self.magnitude_db(f, dt)).collect();
+
+        let mut phases_deg = Vec::<f64>::with_capacity(freqs_hz.len());
+        for &freq_hz in freqs_hz {
+            let mut phase_deg = self.phase_deg(freq_hz, dt);
+            if let Some(&prev_phase_deg) = phases_deg.last() {
+                phase_deg -= 360.0 * ((phase_deg - prev_phase_deg) / 360.0).round();
+            }
+            phases_deg.push(phase_deg);
+        }
+
+        (magnitudes_db, phases_deg)
+    }
+
+    /// Group delay at the given frequency [s], how long a slowly varying signal near that
+    /// frequency lags behind the input. At 0 Hz this is the lag on a steady trend.
+    /// For a polynomial p, delay = Re(sum(k p_k e^-jwk) / sum(p_k e^-jwk)) samples, and
+    /// the filter's delay is the numerator's minus the denominator's.
+    pub fn group_delay_s(&self, freq_hz: f64, dt: f64) -> f64 {
+        let omega = 2.0 * PI * freq_hz * dt;
+
+        (polynomial_delay(&self.b, omega) - polynomial_delay(&self.a, omega)) * dt
+    }
+
+    /// Output for a unit impulse at k = 0
+    pub fn impulse_response(&self, num_samples: usize) -> Vec<f64> {
+        let impulse: Vec<f64> = (0..num_samples)
+            .map(|k| if k == 0 { 1.0 } else { 0.0 })
+            .collect();
+        self.filter(&impulse)
+    }
+
+    /// Output for a unit step at k = 0
+    pub fn step_response(&self, num_samples: usize) -> Vec<f64> {
+        self.filter(&vec![1.0; num_samples])
+    }
+
+    /// Run the difference equation over data, starting at rest
+    pub fn filter(&self, data: &[f64]) -> Vec<f64> {
+        let mut outputs = Vec::<f64>::with_capacity(data.len());
+
+        for k in 0..data.len() {
+            let feed_forward: f64 = (0..self.b.len().min(k + 1))
+                .map(|i| self.b[i] * data[k - i])
+                .sum();
+            let feedback: f64 = (1..self.a.len().min(k + 1))
+                .map(|i| self.a[i] * outputs[k - i])
+                .sum();
+            outputs.push((feed_forward - feedback) / self.a[0]);
+        }
+
+        outputs
+    }
+}
+
+/// Steady state Kalman gain for a scalar model, x_k = a x_k-1 + w, z_k = h x_k + v,
+/// with process noise variance q and measurement noise variance r.
+/// The predicted covariance P solves the scalar Riccati equation
+/// P = a^2 P r / (h^2 P + r) + q, i.e. h^2 P^2 + (r (1 - a^2) - q h^2) P - q r = 0
+/// K = P h / (h^2 P + r)
+pub fn steady_state_kalman_gain(a: f64, q: f64, h: f64, r: f64) -> f64 {
+    assert!(
+        (q > 0.0) && (r > 0.0) && (h != 0.0),
+        "steady_state_kalman_gain: q and r must be greater than zero, and h must not be zero."
+    );
+
+    let b = r * (1.0 - a.powi(2)) - q * h.powi(2);
+    let prd_cvr = (-b + (b.powi(2) + 4.0 * h.powi(2) * q * r).sqrt()) / (2.0 * h.powi(2));
+
+    prd_cvr * h / (h.powi(2) * prd_cvr + r)
+}
+
+/// num_pts frequencies spaced evenly on a log scale from start_hz to end_hz, for Bode plots
+pub fn log_spaced_frequencies(start_hz: f64, end_hz: f64, num_pts: usize) -> Vec<f64> {
+    assert!(
+        (start_hz > 0.0) && (end_hz > start_hz) && (num_pts > 1),
+        "log_spaced_frequencies: Need 0 < start_hz < end_hz and at least two points."
+    );
+
+    let log_step = (end_hz / start_hz).ln() / (num_pts - 1) as f64;
+    (0..num_pts)
+        .map(|i| start_hz * (log_step * i as f64).exp())
+        .collect()
+}
+
+/// sum(p_k e^-jwk)
+fn polynomial_response(coeffs: &[f64], omega: f64) -> Complex<f64> {
+    coeffs
+        .iter()
+        .enumerate()
+        .map(|(k, &p)| p * unit_phasor(-omega * k as f64))
+        .sum()
+}
+
+/// Group delay of a polynomial in samples, Re(sum(k p_k e^-jwk) / sum(p_k e^-jwk))
+fn polynomial_delay(coeffs: &[f64], omega: f64) -> f64 {
+    let ramp_weighted: Complex<f64> = coeffs
+        .iter()
+        .enumerate()
+        .map(|(k, &p)| k as f64 * p * unit_phasor(-omega * k as f64))
+        .sum();
+
+    (ramp_weighted / polynomial_response(coeffs, omega)).re
+}
+
+/// e^j(angle)
+fn unit_phasor(angle: f64) -> Complex<f64> {
+    Complex::new(angle.cos(), angle.sin())
+}
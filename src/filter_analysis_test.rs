@@ -0,0 +1,77 @@
+use crate::utils::{plot_bode, plot_lines};
+use kalman_filter_for_beginners_rust::filter_analysis::{
+    log_spaced_frequencies, steady_state_kalman_gain, TransferFunction,
+};
+
+pub fn filter_frequency_response_example() {
+    // Sample rate of the sonar examples
+    let dt: f64 = 0.02;
+
+    // Moving average window and alpha as in the textbook examples, plus a scalar random walk
+    // Kalman filter, x_k = x_k-1 + w, z_k = x_k + v
+    let window_size: usize = 10;
+    let alpha: f64 = 0.7;
+    let (a, q, h, r) = (1.0, 1.0, 1.0, 10.0);
+
+    let filters = [
+        (
+            format!("Moving average, n = {}", window_size),
+            TransferFunction::from_moving_average(window_size),
+        ),
+        (
+            format!("Low pass, alpha = {}", alpha),
+            TransferFunction::from_low_pass_1st_order(alpha),
+        ),
+        (
+            format!("Kalman, K = {:.3}", steady_state_kalman_gain(a, q, h, r)),
+            TransferFunction::from_steady_state_kalman(a, q, h, r),
+        ),
+    ];
+
+    // Lag on a steady trend, and the -3 dB frequency found by stepping up from 0 Hz
+    let freqs_hz = log_spaced_frequencies(0.01, 0.5 / dt, 400);
+    for (name, trns_fn) in filters.iter() {
+        let cutoff_freq_hz = freqs_hz
+            .iter()
+            .find(|&&freq| trns_fn.magnitude_db(freq, dt) < -3.0)
+            .copied()
+            .unwrap_or(f64::NAN);
+        println!(
+            "{}: lag {:.3} s, -3 dB at {:.2} Hz",
+            name,
+            trns_fn.group_delay_s(0.0, dt),
+            cutoff_freq_hz
+        );
+    }
+
+    let responses: Vec<(&str, Vec<f64>, Vec<f64>)> = filters
+        .iter()
+        .map(|(name, trns_fn)| {
+            let (magnitudes_db, phases_deg) = trns_fn.bode(&freqs_hz, dt);
+            (name.as_str(), magnitudes_db, phases_deg)
+        })
+        .collect();
+    plot_bode(
+        "./plots/25a_FilterFrequencyResponse.png",
+        "Filter Frequency Response",
+        &freqs_hz,
+        &responses,
+    );
+
+    // Step responses from rest, the moving average settles in exactly n samples while the
+    // low pass and steady state Kalman filters approach the step exponentially
+    let num_samples: usize = 25;
+    let sample_idxs: Vec<f64> = (0..num_samples).map(|k| k as f64).collect();
+    let step_responses: Vec<(&str, Vec<f64>)> = filters
+        .iter()
+        .map(|(name, trns_fn)| (name.as_str(), trns_fn.step_response(num_samples)))
+        .collect();
+    plot_lines(
+        "./plots/25b_FilterStepResponse.png",
+        "Filter Step Response",
+        "Sample",
+        "Output",
+        &sample_idxs,
+        &step_responses,
+    );
+}
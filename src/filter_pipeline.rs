@@ -0,0 +1,616 @@
+use crate::alpha_beta_filters::AlphaBetaFilter;
+use crate::biquad_filters::BiquadCascade;
+use crate::kalman_filter::{ScalarKalmanFilter, SystemModel};
+use crate::outlier_filters::{HampelFilter, MedianFilter};
+use crate::recursive_filters::{
+    Filter, HighPassFilter1stOrder, LowPassFilter1stOrder, MovingAverageFilter,
+};
+use nalgebra::{DMatrix, DVector};
+use std::fmt;
+use std::str::FromStr;
+
+/// Parameters of one pipeline stage, enough to construct its filter, so a pipeline can be
+/// written down as data, e.g. loaded from a settings file, and rebuilt later.
+/// Parsed from text as the stage kind, the variant name in snake_case, then its fields as
+/// key=value, e.g. "low_pass alpha=0.7" or "hampel window_size=7 num_mads=3". Vectors are
+/// comma separated, matrices are rows of those separated by semicolons, e.g. a=1,0.02;0,1
+/// Parsing also checks the values with validate(), so build() won't panic on parsed text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StageConfig {
+    /// MovingAverageFilter::new
+    MovingAverage { window_size: usize },
+    /// LowPassFilter1stOrder::new
+    LowPass { alpha: f64 },
+    /// LowPassFilter1stOrder::from_cutoff_frequency
+    LowPassCutoff { cutoff_freq_hz: f64, dt: f64 },
+    /// HighPassFilter1stOrder::from_cutoff_frequency
+    HighPassCutoff { cutoff_freq_hz: f64, dt: f64 },
+    /// BiquadCascade::butterworth_low_pass
+    ButterworthLowPass {
+        order: usize,
+        cutoff_freq_hz: f64,
+        dt: f64,
+    },
+    /// MedianFilter::new
+    Median { window_size: usize },
+    /// HampelFilter::new
+    Hampel { window_size: usize, num_mads: f64 },
+    /// AlphaBetaFilter::new
+    AlphaBeta { alpha: f64, beta: f64, dt: f64 },
+    /// ScalarKalmanFilter::new, with the system model matrices A, Q, H and a scalar R
+    ScalarKalman {
+        a: DMatrix<f64>,
+        q: DMatrix<f64>,
+        h: DMatrix<f64>,
+        r: f64,
+        initial_est_x: DVector<f64>,
+        initial_est_p: DMatrix<f64>,
+        output_state_idx: usize,
+    },
+}
+
+impl StageConfig {
+    /// Check the parameters meet the filter constructor's requirements, e.g. a window size
+    /// greater than zero or Kalman filter matrices of matching sizes
+    pub fn validate(&self) -> Result<(), ConfigParseError> {
+        match self {
+            Self::MovingAverage { window_size } | Self::Median { window_size } => {
+                check_value(*window_size > 0, "window_size", window_size)
+            }
+            Self::LowPass { alpha } => {
+                check_value((*alpha > 0.0) && (*alpha < 1.0), "alpha", alpha)
+            }
+            Self::LowPassCutoff { cutoff_freq_hz, dt }
+            | Self::HighPassCutoff { cutoff_freq_hz, dt } => {
+                check_value(
+                    is_positive(*cutoff_freq_hz),
+                    "cutoff_freq_hz",
+                    cutoff_freq_hz,
+                )?;
+                check_value(is_positive(*dt), "dt", dt)
+            }
+            Self::ButterworthLowPass {
+                order,
+                cutoff_freq_hz,
+                dt,
+            } => {
+                check_value(*order > 0, "order", order)?;
+                check_value(is_positive(*dt), "dt", dt)?;
+                // Below the Nyquist frequency
+                check_value(
+                    (*cutoff_freq_hz > 0.0) && (cutoff_freq_hz * dt < 0.5),
+                    "cutoff_freq_hz",
+                    cutoff_freq_hz,
+                )
+            }
+            Self::Hampel {
+                window_size,
+                num_mads,
+            } => {
+                check_value(*window_size > 0, "window_size", window_size)?;
+                check_value(*num_mads > 0.0, "num_mads", num_mads)
+            }
+            Self::AlphaBeta { alpha, beta, dt } => {
+                check_value((*alpha > 0.0) && (*alpha < 2.0), "alpha", alpha)?;
+                check_value((*beta > 0.0) && (*beta < 4.0 - 2.0 * alpha), "beta", beta)?;
+                check_value(is_positive(*dt), "dt", dt)
+            }
+            Self::ScalarKalman {
+                a,
+                q,
+                h,
+                r,
+                initial_est_x,
+                initial_est_p,
+                output_state_idx,
+            } => {
+                // Number of states from A; the other matrices must match it
+                let num_states = a.nrows();
+                let shape =
+                    |matrix: &DMatrix<f64>| format!("{}x{}", matrix.nrows(), matrix.ncols());
+                check_value((num_states > 0) && a.is_square(), "a", shape(a))?;
+                check_value(q.shape() == (num_states, num_states), "q", shape(q))?;
+                check_value(h.shape() == (1, num_states), "h", shape(h))?;
+                check_value(is_positive(*r), "r", r)?;
+                check_value(
+                    initial_est_x.len() == num_states,
+                    "initial_est_x",
+                    format!("{}x1", initial_est_x.len()),
+                )?;
+                check_value(
+                    initial_est_p.shape() == (num_states, num_states),
+                    "initial_est_p",
+                    shape(initial_est_p),
+                )?;
+                check_value(
+                    *output_state_idx < num_states,
+                    "output_state_idx",
+                    output_state_idx,
+                )
+            }
+        }
+    }
+
+    /// Construct the configured filter; panics as its constructor does if validate() fails
+    pub fn build(&self) -> Box<dyn Filter> {
+        match self.clone() {
+            Self::MovingAverage { window_size } => Box::new(MovingAverageFilter::new(window_size)),
+            Self::LowPass { alpha } => Box::new(LowPassFilter1stOrder::new(alpha)),
+            Self::LowPassCutoff { cutoff_freq_hz, dt } => Box::new(
+                LowPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt),
+            ),
+            Self::HighPassCutoff { cutoff_freq_hz, dt } => Box::new(
+                HighPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt),
+            ),
+            Self::ButterworthLowPass {
+                order,
+                cutoff_freq_hz,
+                dt,
+            } => Box::new(BiquadCascade::butterworth_low_pass(
+                order,
+                cutoff_freq_hz,
+                dt,
+            )),
+            Self::Median { window_size } => Box::new(MedianFilter::new(window_size)),
+            Self::Hampel {
+                window_size,
+                num_mads,
+            } => Box::new(HampelFilter::new(window_size, num_mads)),
+            Self::AlphaBeta { alpha, beta, dt } => Box::new(AlphaBetaFilter::new(alpha, beta, dt)),
+            Self::ScalarKalman {
+                a,
+                q,
+                h,
+                r,
+                initial_est_x,
+                initial_est_p,
+                output_state_idx,
+            } => Box::new(ScalarKalmanFilter::new(
+                SystemModel::new(a, q, h, DMatrix::from_element(1, 1, r)),
+                initial_est_x,
+                initial_est_p,
+                output_state_idx,
+            )),
+        }
+    }
+}
+
+impl FromStr for StageConfig {
+    type Err = ConfigParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let kind = words.next().ok_or(ConfigParseError::MissingStageKind)?;
+        let mut params = StageParams::parse(words)?;
+
+        let config = match kind {
+            "moving_average" => Self::MovingAverage {
+                window_size: params.take("window_size")?,
+            },
+            "low_pass" => Self::LowPass {
+                alpha: params.take("alpha")?,
+            },
+            "low_pass_cutoff" => Self::LowPassCutoff {
+                cutoff_freq_hz: params.take("cutoff_freq_hz")?,
+                dt: params.take("dt")?,
+            },
+            "high_pass_cutoff" => Self::HighPassCutoff {
+                cutoff_freq_hz: params.take("cutoff_freq_hz")?,
+                dt: params.take("dt")?,
+            },
+            "butterworth_low_pass" => Self::ButterworthLowPass {
+                order: params.take("order")?,
+                cutoff_freq_hz: params.take("cutoff_freq_hz")?,
+                dt: params.take("dt")?,
+            },
+            "median" => Self::Median {
+                window_size: params.take("window_size")?,
+            },
+            "hampel" => Self::Hampel {
+                window_size: params.take("window_size")?,
+                num_mads: params.take("num_mads")?,
+            },
+            "alpha_beta" => Self::AlphaBeta {
+                alpha: params.take("alpha")?,
+                beta: params.take("beta")?,
+                dt: params.take("dt")?,
+            },
+            "scalar_kalman" => Self::ScalarKalman {
+                a: params.take_matrix("a")?,
+                q: params.take_matrix("q")?,
+                h: params.take_matrix("h")?,
+                r: params.take("r")?,
+                initial_est_x: params.take_vector("initial_est_x")?,
+                initial_est_p: params.take_matrix("initial_est_p")?,
+                output_state_idx: params.take("output_state_idx")?,
+            },
+            _ => return Err(ConfigParseError::UnknownStageKind(kind.to_string())),
+        };
+
+        params.finish()?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Why a StageConfig or FilterPipeline couldn't be parsed from text or built from config
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigParseError {
+    MissingStageKind,
+    UnknownStageKind(String),
+    /// A parameter not written as key=value
+    MalformedParameter(String),
+    MissingParameter(String),
+    /// A parameter the stage kind doesn't have, or one given twice
+    UnexpectedParameter(String),
+    InvalidValue {
+        parameter: String,
+        value: String,
+    },
+    /// A pipeline line not written as "name: stage"
+    MissingStageName,
+    DuplicateStageName(String),
+    NoStages,
+    /// Error on a line of a pipeline, line_num counts from 1
+    Line {
+        line_num: usize,
+        error: Box<ConfigParseError>,
+    },
+}
+
+impl fmt::Display for ConfigParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingStageKind => write!(f, "FilterPipeline: Stage kind is missing."),
+            Self::UnknownStageKind(kind) => {
+                write!(f, "FilterPipeline: Unknown stage kind '{}'.", kind)
+            }
+            Self::MalformedParameter(word) => write!(
+                f,
+                "FilterPipeline: Parameter '{}' must be written as key=value.",
+                word
+            ),
+            Self::MissingParameter(key) => {
+                write!(f, "FilterPipeline: Parameter '{}' is missing.", key)
+            }
+            Self::UnexpectedParameter(key) => write!(
+                f,
+                "FilterPipeline: Parameter '{}' is unknown or given twice.",
+                key
+            ),
+            Self::InvalidValue { parameter, value } => write!(
+                f,
+                "FilterPipeline: Parameter '{}' has an invalid value '{}'.",
+                parameter, value
+            ),
+            Self::MissingStageName => write!(
+                f,
+                "FilterPipeline: Stage must be written as \"name: stage\"."
+            ),
+            Self::DuplicateStageName(name) => write!(
+                f,
+                "FilterPipeline: Stage names must be unique, found '{}' twice.",
+                name
+            ),
+            Self::NoStages => write!(
+                f,
+                "FilterPipeline: Number of stages must be greater than zero."
+            ),
+            Self::Line { line_num, error } => write!(f, "Line {}: {}", line_num, error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigParseError {}
+
+/// key=value parameters of one stage, removed as they're read so leftovers can be reported
+struct StageParams<'a> {
+    params: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> StageParams<'a> {
+    fn parse(words: impl Iterator<Item = &'a str>) -> Result<Self, ConfigParseError> {
+        let params = words
+            .map(|word| {
+                word.split_once('=')
+                    .ok_or_else(|| ConfigParseError::MalformedParameter(word.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { params })
+    }
+
+    fn take_str(&mut self, key: &str) -> Result<&'a str, ConfigParseError> {
+        let idx = self
+            .params
+            .iter()
+            .position(|(param_key, _)| *param_key == key)
+            .ok_or_else(|| ConfigParseError::MissingParameter(key.to_string()))?;
+
+        Ok(self.params.remove(idx).1)
+    }
+
+    fn take<T: FromStr>(&mut self, key: &str) -> Result<T, ConfigParseError> {
+        let value = self.take_str(key)?;
+        value.parse().map_err(|_| invalid_value(key, value))
+    }
+
+    /// Comma separated elements
+    fn take_vector(&mut self, key: &str) -> Result<DVector<f64>, ConfigParseError> {
+        let value = self.take_str(key)?;
+        let elements = parse_elements(value).ok_or_else(|| invalid_value(key, value))?;
+        Ok(DVector::from_vec(elements))
+    }
+
+    /// Rows separated by semicolons, each row's elements by commas
+    fn take_matrix(&mut self, key: &str) -> Result<DMatrix<f64>, ConfigParseError> {
+        let value = self.take_str(key)?;
+        let rows = value
+            .split(';')
+            .map(parse_elements)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid_value(key, value))?;
+
+        let num_cols = rows[0].len();
+        if rows.iter().any(|row| row.len() != num_cols) {
+            return Err(invalid_value(key, value));
+        }
+        Ok(DMatrix::from_row_slice(
+            rows.len(),
+            num_cols,
+            &rows.concat(),
+        ))
+    }
+
+    /// Every parameter must have been read
+    fn finish(self) -> Result<(), ConfigParseError> {
+        match self.params.first() {
+            Some((key, _)) => Err(ConfigParseError::UnexpectedParameter(key.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_elements(text: &str) -> Option<Vec<f64>> {
+    text.split(',')
+        .map(|element| element.parse().ok())
+        .collect()
+}
+
+fn invalid_value(key: &str, value: &str) -> ConfigParseError {
+    ConfigParseError::InvalidValue {
+        parameter: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn check_value(
+    is_valid: bool,
+    key: &str,
+    value: impl fmt::Display,
+) -> Result<(), ConfigParseError> {
+    if is_valid {
+        Ok(())
+    } else {
+        Err(invalid_value(key, &value.to_string()))
+    }
+}
+
+/// Greater than zero and finite
+fn is_positive(value: f64) -> bool {
+    (value > 0.0) && value.is_finite()
+}
+
+struct PipelineStage {
+    name: String,
+    filter: Box<dyn Filter>,
+    config: Option<StageConfig>,
+}
+
+/// Recursive filters in series, run as one filter, e.g. a median filter to remove dropouts,
+/// then a low pass filter, then a Kalman filter. Each stage's output is the next stage's
+/// input, and every stage's latest output can be tapped off by name.
+/// A pipeline can also be parsed from text, one "name: stage" per line in processing order,
+/// each stage as parsed by StageConfig. Blank lines and lines starting with # are skipped.
+///
+/// ```
+/// use kalman_filter_for_beginners_rust::filter_pipeline::{FilterPipeline, StageConfig};
+/// use kalman_filter_for_beginners_rust::outlier_filters::MedianFilter;
+///
+/// let mut pipeline = FilterPipeline::builder()
+///     .stage("median", MedianFilter::new(5))
+///     .stage_from_config("lpf", StageConfig::LowPass { alpha: 0.7 })
+///     .build();
+/// pipeline.update(1.0);
+/// assert_eq!(pipeline.get_stage_output("median"), Some(1.0));
+///
+/// let pipeline: FilterPipeline = "
+///     ## Remove dropouts, then smooth
+///     median: median window_size=5
+///     lpf: low_pass alpha=0.7
+/// "
+/// .parse()
+/// .unwrap();
+/// assert_eq!(pipeline.get_stage_names(), ["median", "lpf"]);
+/// ```
+pub struct FilterPipeline {
+    stages: Vec<PipelineStage>,
+}
+
+impl FilterPipeline {
+    pub fn builder() -> FilterPipelineBuilder {
+        FilterPipelineBuilder { stages: Vec::new() }
+    }
+
+    /// Pipeline built entirely from config, stages as (name, config) in processing order.
+    /// Every config is validated first, so an invalid one is an error rather than a panic.
+    pub fn from_config(stage_configs: &[(&str, StageConfig)]) -> Result<Self, ConfigParseError> {
+        if stage_configs.is_empty() {
+            return Err(ConfigParseError::NoStages);
+        }
+        for (idx, (name, config)) in stage_configs.iter().enumerate() {
+            if stage_configs[..idx]
+                .iter()
+                .any(|(other_name, _)| other_name == name)
+            {
+                return Err(ConfigParseError::DuplicateStageName(name.to_string()));
+            }
+            config.validate()?;
+        }
+
+        Ok(stage_configs
+            .iter()
+            .fold(Self::builder(), |builder, (name, config)| {
+                builder.stage_from_config(name, config.clone())
+            })
+            .build())
+    }
+
+    pub fn update(&mut self, data: f64) {
+        let mut stage_input = data;
+        for stage in self.stages.iter_mut() {
+            stage.filter.update(stage_input);
+            stage_input = stage.filter.get_output();
+        }
+    }
+
+    /// Output of the last stage
+    pub fn get_output(&self) -> f64 {
+        self.stages.last().unwrap().filter.get_output()
+    }
+
+    /// Latest output of the named stage, None if there is no stage with that name
+    pub fn get_stage_output(&self, name: &str) -> Option<f64> {
+        self.stages
+            .iter()
+            .find(|stage| stage.name == name)
+            .map(|stage| stage.filter.get_output())
+    }
+
+    /// Latest output of every stage, in processing order
+    pub fn get_stage_outputs(&self) -> Vec<f64> {
+        self.stages
+            .iter()
+            .map(|stage| stage.filter.get_output())
+            .collect()
+    }
+
+    pub fn get_stage_names(&self) -> Vec<&str> {
+        self.stages
+            .iter()
+            .map(|stage| stage.name.as_str())
+            .collect()
+    }
+
+    /// Config of every stage, in processing order; None for stages added as a filter
+    pub fn get_stage_configs(&self) -> Vec<Option<&StageConfig>> {
+        self.stages
+            .iter()
+            .map(|stage| stage.config.as_ref())
+            .collect()
+    }
+}
+
+impl FromStr for FilterPipeline {
+    type Err = ConfigParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut stage_configs = Vec::<(&str, StageConfig)>::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_error = |error| ConfigParseError::Line {
+                line_num: line_idx + 1,
+                error: Box::new(error),
+            };
+            let (name, stage) = line
+                .split_once(':')
+                .map(|(name, stage)| (name.trim(), stage))
+                .filter(|(name, _)| !name.is_empty())
+                .ok_or_else(|| line_error(ConfigParseError::MissingStageName))?;
+            if stage_configs
+                .iter()
+                .any(|(other_name, _)| *other_name == name)
+            {
+                return Err(line_error(ConfigParseError::DuplicateStageName(
+                    name.to_string(),
+                )));
+            }
+
+            stage_configs.push((name, stage.parse().map_err(line_error)?));
+        }
+
+        Self::from_config(&stage_configs)
+    }
+}
+
+impl Filter for FilterPipeline {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_output()
+    }
+
+    fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.filter.reset();
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.stages[0].filter.is_initialized()
+    }
+}
+
+/// Adds stages to a FilterPipeline in processing order, see FilterPipeline::builder
+pub struct FilterPipelineBuilder {
+    stages: Vec<PipelineStage>,
+}
+
+impl FilterPipelineBuilder {
+    /// Add any recursive filter as the next stage
+    pub fn stage(self, name: &str, filter: impl Filter + 'static) -> Self {
+        self.push_stage(name, Box::new(filter), None)
+    }
+
+    /// Add the configured filter as the next stage
+    pub fn stage_from_config(self, name: &str, config: StageConfig) -> Self {
+        self.push_stage(name, config.build(), Some(config))
+    }
+
+    pub fn build(self) -> FilterPipeline {
+        assert!(
+            !self.stages.is_empty(),
+            "FilterPipeline: Number of stages must be greater than zero."
+        );
+
+        FilterPipeline {
+            stages: self.stages,
+        }
+    }
+
+    fn push_stage(
+        mut self,
+        name: &str,
+        filter: Box<dyn Filter>,
+        config: Option<StageConfig>,
+    ) -> Self {
+        assert!(
+            self.stages.iter().all(|stage| stage.name != name),
+            "FilterPipeline: Stage names must be unique, found '{}' twice.",
+            name
+        );
+
+        self.stages.push(PipelineStage {
+            name: name.to_string(),
+            filter,
+            config,
+        });
+        self
+    }
+}
@@ -0,0 +1,240 @@
+use crate::recursive_filters::Filter;
+use num_traits::{NumCast, One, PrimInt, Signed, Zero};
+
+/// Signed fixed point number with all bits but the sign as fraction, range [-1, 1)
+/// Q15: i16, one LSB = 2^-15; Q31: i32, one LSB = 2^-31
+///
+/// Arithmetic runs in a wider accumulator, so intermediate sums and products never overflow.
+/// Rounding: results are rounded to the nearest LSB, with ties rounded up (toward +inf).
+/// Saturation: results outside the representable range are clamped to MIN or MAX.
+pub trait FixedPoint: PrimInt + Signed {
+    /// Accumulator, wide enough for a product of two values or a sum over a long window
+    type Acc: PrimInt + Signed + From<Self>;
+    /// Number of fractional bits
+    const FRAC_BITS: u32;
+
+    /// Nearest fixed point value to a real number, saturating outside [-1, 1)
+    fn from_real(value: f64) -> Self {
+        let scaled = (value * (1u64 << Self::FRAC_BITS) as f64).round();
+        let min = Self::min_value().to_f64().unwrap();
+        let max = Self::max_value().to_f64().unwrap();
+        <Self as NumCast>::from(scaled.clamp(min, max)).unwrap()
+    }
+
+    /// Real number represented by a fixed point value
+    fn to_real(self) -> f64 {
+        self.to_f64().unwrap() / (1u64 << Self::FRAC_BITS) as f64
+    }
+
+    /// Clamp an accumulator value to the representable range
+    fn saturate(acc: Self::Acc) -> Self {
+        let min = widen(Self::min_value());
+        let max = widen(Self::max_value());
+        <Self as NumCast>::from(acc.clamp(min, max)).unwrap()
+    }
+}
+
+/// Q15 fixed point, 1 sign bit and 15 fraction bits
+pub type Q15 = i16;
+/// Q31 fixed point, 1 sign bit and 31 fraction bits
+pub type Q31 = i32;
+
+impl FixedPoint for i16 {
+    type Acc = i64;
+    const FRAC_BITS: u32 = 15;
+}
+
+impl FixedPoint for i32 {
+    type Acc = i128;
+    const FRAC_BITS: u32 = 31;
+}
+
+/// Convert to the accumulator type, exactly
+fn widen<Q: FixedPoint>(value: Q) -> Q::Acc {
+    <Q::Acc as From<Q>>::from(value)
+}
+
+/// Divide, rounding to nearest with ties up: floor((2 * num + den) / (2 * den)), den > 0
+fn round_div<A: PrimInt + Signed>(num: A, den: A) -> A {
+    let two = A::one() + A::one();
+    let (n, d) = (two * num + den, two * den);
+    let quotient = n / d;
+    // Integer division truncates toward zero; step down to the floor for negative results
+    if n % d != A::zero() && n < A::zero() {
+        quotient - A::one()
+    } else {
+        quotient
+    }
+}
+
+/// Arithmetic shift right by bits, rounding to nearest with ties up
+fn round_shift_right<A: PrimInt + Signed>(value: A, bits: u32) -> A {
+    if bits == 0 {
+        value
+    } else {
+        (value + (A::one() << (bits as usize - 1))) >> bits as usize
+    }
+}
+
+/// Fixed point version of MovingAverageFilter, for processors without an FPU.
+/// Keeps an exact running sum in the accumulator, so there is no drift from repeatedly
+/// adding and removing rounded values. Like MovingAverageFilter, the buffer is initialized
+/// with the first data value.
+pub struct FixedMovingAverageFilter<Q: FixedPoint> {
+    avg: Q,
+    sum: Q::Acc,
+    window_size: usize, // 'n' in formula
+    data_buff: Option<Vec<Q>>,
+    oldest_data_idx: usize,
+}
+
+impl<Q: FixedPoint> FixedMovingAverageFilter<Q> {
+    pub fn new(window_size: usize) -> Self {
+        assert!(
+            window_size > 0,
+            "FixedMovingAverageFilter: Window size must be greater than zero."
+        );
+
+        Self {
+            avg: Q::zero(),
+            sum: Q::Acc::zero(),
+            window_size,
+            data_buff: None,
+            oldest_data_idx: 0,
+        }
+    }
+
+    fn window_size_acc(&self) -> Q::Acc {
+        <Q::Acc as NumCast>::from(self.window_size)
+            .expect("FixedMovingAverageFilter: Window size too large for the accumulator")
+    }
+
+    pub fn update(&mut self, data: Q) {
+        let window_size = self.window_size_acc();
+
+        if let Some(ref mut data_buff) = self.data_buff {
+            // Update running sum
+            let oldest_data = data_buff[self.oldest_data_idx];
+            self.sum = self.sum - widen(oldest_data) + widen(data);
+
+            // Book keeping to remove oldest and add newest data point to buffer
+            data_buff[self.oldest_data_idx] = data;
+            self.oldest_data_idx = (self.oldest_data_idx + 1) % self.window_size;
+        } else {
+            // Inititially there are not enough data points for rolling average
+            // This implementation initializes the buffer with the first data value
+            self.data_buff = Some(vec![data; self.window_size]);
+            self.sum = widen(data) * window_size;
+        }
+
+        self.avg = Q::saturate(round_div(self.sum, window_size));
+    }
+
+    pub fn get_average(&self) -> Q {
+        self.avg
+    }
+}
+
+impl<Q: FixedPoint> Filter<Q> for FixedMovingAverageFilter<Q> {
+    fn update(&mut self, data: Q) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> Q {
+        self.get_average()
+    }
+
+    fn reset(&mut self) {
+        self.avg = Q::zero();
+        self.sum = Q::Acc::zero();
+        self.data_buff = None;
+        self.oldest_data_idx = 0;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// Fixed point version of LowPassFilter1stOrder, for processors without an FPU.
+/// avg = alpha * avg + (1 - alpha) * data, computed as avg + (1 - alpha) * (data - avg)
+/// so that only one rounding happens per update.
+pub struct FixedLowPassFilter1stOrder<Q: FixedPoint> {
+    avg: Q,
+    alpha: Q,
+    initialized: bool,
+}
+
+impl<Q: FixedPoint> FixedLowPassFilter1stOrder<Q> {
+    /// alpha: smoothing factor in the same fixed point format, 0 < alpha < 1
+    pub fn new(alpha: Q) -> Self {
+        assert!(
+            alpha > Q::zero(),
+            "FixedLowPassFilter1stOrder: Smoothing factor, alpha, \
+             must be between zero and one, 0.0 < alpha < 1.0"
+        );
+
+        Self {
+            avg: Q::zero(),
+            alpha,
+            initialized: false,
+        }
+    }
+
+    /// alpha: smoothing factor, 0.0 < alpha < 1.0, rounded to the nearest fixed point value,
+    /// at most one LSB below one
+    pub fn from_real_alpha(alpha: f64) -> Self {
+        assert!(
+            (alpha > 0.0) && (alpha < 1.0),
+            "FixedLowPassFilter1stOrder: Smoothing factor, alpha, \
+             must be between zero and one, 0.0 < alpha < 1.0"
+        );
+
+        let alpha_fixed = Q::from_real(alpha);
+        assert!(
+            alpha_fixed > Q::zero(),
+            "FixedLowPassFilter1stOrder: Smoothing factor, alpha, {} rounds to zero \
+             with {} fraction bits.",
+            alpha,
+            Q::FRAC_BITS
+        );
+
+        Self::new(alpha_fixed)
+    }
+
+    pub fn update(&mut self, data: Q) {
+        if !(self.initialized) {
+            self.avg = data;
+            self.initialized = true;
+        }
+
+        let one = Q::Acc::one() << Q::FRAC_BITS as usize;
+        let avg = widen(self.avg);
+        let step = (one - widen(self.alpha)) * (widen(data) - avg);
+
+        self.avg = Q::saturate(avg + round_shift_right(step, Q::FRAC_BITS));
+    }
+
+    pub fn get_average(&self) -> Q {
+        self.avg
+    }
+}
+
+impl<Q: FixedPoint> Filter<Q> for FixedLowPassFilter1stOrder<Q> {
+    fn update(&mut self, data: Q) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> Q {
+        self.get_average()
+    }
+
+    fn reset(&mut self) {
+        self.avg = Q::zero();
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
@@ -1,6 +1,12 @@
 #![allow(non_snake_case)]
 
+use crate::recursive_filters::Filter;
 use nalgebra::{DMatrix, DVector};
+use std::collections::VecDeque;
+
+/// State transition and process noise for a time step, (A, Q) = f(dt), for measurements at
+/// arbitrary times, see KalmanFilter::with_out_of_sequence_updates
+pub type DiscretizationFn = Box<dyn Fn(f64) -> (DMatrix<f64>, DMatrix<f64>)>;
 
 /// Based on definition in Chapter 8, pg. 66
 /// State
@@ -51,6 +57,49 @@ pub struct KalmanFilter {
     // Estimation
     est_x: DVector<f64>,
     est_cvr_P: DMatrix<f64>,
+    // Timestamped measurements, None unless with_out_of_sequence_updates
+    out_of_sequence: Option<OutOfSequenceState>,
+}
+
+/// How KalmanFilter::update_at applies a measurement taken before the latest one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutOfSequenceMethod {
+    /// Drop it, as a filter without a history has to
+    Discard,
+    /// Roll back to the buffered estimate just before the measurement, apply it, then apply
+    /// every later buffered measurement again. Exact, costs one update per later measurement.
+    Refilter,
+    /// Retrodict the current estimate back to the measurement time and update the current
+    /// estimate with it in one step, Bar-Shalom's algorithm A1. Costs one update; exact for a
+    /// measurement taken since the one before the latest, approximate for older ones.
+    Retrodiction,
+}
+
+/// Buffered estimate after applying a measurement
+struct HistoryEntry {
+    time_s: f64,
+    // None for the oldest entry, whose measurement can't be applied again
+    measurement_z: Option<DVector<f64>>,
+    est_x: DVector<f64>,
+    est_cvr_P: DMatrix<f64>,
+}
+
+/// Innovation of the latest in sequence update, which Retrodiction needs to account for the
+/// process noise since an out of sequence measurement
+struct Innovation {
+    innovation_y: DVector<f64>,
+    innovation_cvr_S: DMatrix<f64>,
+    klmn_gain_K: DMatrix<f64>,
+}
+
+struct OutOfSequenceState {
+    discretize: DiscretizationFn,
+    method: OutOfSequenceMethod,
+    history_len: usize,
+    // Oldest first; never empty, the last entry is the current estimate
+    history: VecDeque<HistoryEntry>,
+    // None until the first update_at
+    latest_innovation: Option<Innovation>,
 }
 
 impl KalmanFilter {
@@ -84,9 +133,42 @@ impl KalmanFilter {
             klmn_gain_K,
             est_x: initial_est_state_x,
             est_cvr_P: initial_est_covar_P,
+            out_of_sequence: None,
         }
     }
 
+    /// Take timestamped measurements with update_at, which may arrive out of sequence, e.g.
+    /// from sensors with different latencies. The filter's time starts at 0 s.
+    /// discretize: A and Q for a time step of dt seconds, used in place of the system model's
+    ///     A and Q; Retrodiction also calls it with negative dt, so A must be invertible
+    /// history_len: number of measurements buffered; a measurement older than all of them is
+    ///     discarded
+    pub fn with_out_of_sequence_updates(
+        mut self,
+        discretize: impl Fn(f64) -> (DMatrix<f64>, DMatrix<f64>) + 'static,
+        method: OutOfSequenceMethod,
+        history_len: usize,
+    ) -> Self {
+        assert!(
+            history_len > 0,
+            "KalmanFilter: History length must be greater than zero."
+        );
+
+        self.out_of_sequence = Some(OutOfSequenceState {
+            discretize: Box::new(discretize),
+            method,
+            history_len,
+            history: VecDeque::from([HistoryEntry {
+                time_s: 0.0,
+                measurement_z: None,
+                est_x: self.est_x.clone(),
+                est_cvr_P: self.est_cvr_P.clone(),
+            }]),
+            latest_innovation: None,
+        });
+        self
+    }
+
     // All equations based on Figure 5.1
     // State Prediction
     fn predict_state(prev_est_x: &DVector<f64>, state_trns_A: &DMatrix<f64>) -> DVector<f64> {
@@ -94,7 +176,7 @@ impl KalmanFilter {
     }
 
     // Error Covariance Prediction
-    fn predict_error_covariance(
+    pub(crate) fn predict_error_covariance(
         prev_est_cvr_P: &DMatrix<f64>,
         state_trns_A: &DMatrix<f64>,
         prcs_cvr_Q: &DMatrix<f64>,
@@ -103,7 +185,7 @@ impl KalmanFilter {
     }
 
     // Kalman Gain
-    fn calculate_kalman_gain(
+    pub(crate) fn calculate_kalman_gain(
         prd_cvr_P: &DMatrix<f64>,
         state_to_meas_H: &DMatrix<f64>,
         meas_cvr_R: &DMatrix<f64>,
@@ -129,7 +211,7 @@ impl KalmanFilter {
     }
 
     // Error Covariance Estimate
-    fn estimate_error_convariance(
+    pub(crate) fn estimate_error_convariance(
         prd_cvr_P: &DMatrix<f64>,
         klmn_gain_K: &DMatrix<f64>,
         state_to_meas_H: &DMatrix<f64>,
@@ -137,7 +219,196 @@ impl KalmanFilter {
         prd_cvr_P - klmn_gain_K * state_to_meas_H * prd_cvr_P
     }
 
+    /// Not available with with_out_of_sequence_updates, use update_at
     pub fn update(&mut self, measurement_z: DVector<f64>) {
+        self.assert_in_sequence_mode("update");
+
+        self.correct(measurement_z);
+        self.predict();
+    }
+
+    /// Advance one step without a measurement, e.g. a missed detection; the prediction
+    /// becomes the estimate. Not available with with_out_of_sequence_updates.
+    pub fn update_without_measurement(&mut self) {
+        self.assert_in_sequence_mode("update_without_measurement");
+
+        self.est_x = self.prd_x.clone();
+        self.est_cvr_P = self.prd_cvr_P.clone();
+        self.predict();
+    }
+
+    /// Update with a measurement taken at time_s [s], predicting over the time since the
+    /// latest measurement. A measurement older than that is handled by the
+    /// OutOfSequenceMethod; the estimate stays at the latest measurement's time.
+    /// Returns false if the measurement was discarded.
+    /// Needs with_out_of_sequence_updates.
+    pub fn update_at(&mut self, measurement_z: DVector<f64>, time_s: f64) -> bool {
+        let Some(state) = self.out_of_sequence.as_ref() else {
+            panic!("KalmanFilter: update_at needs with_out_of_sequence_updates.");
+        };
+        let latest_time_s = state.history.back().unwrap().time_s;
+        let oldest_time_s = state.history.front().unwrap().time_s;
+        let method = state.method;
+
+        if time_s >= latest_time_s {
+            self.step(measurement_z.clone(), time_s - latest_time_s);
+            self.push_history(measurement_z, time_s);
+            return true;
+        }
+        if time_s < oldest_time_s {
+            return false;
+        }
+
+        match method {
+            OutOfSequenceMethod::Discard => false,
+            OutOfSequenceMethod::Refilter => {
+                self.refilter(measurement_z, time_s);
+                true
+            }
+            OutOfSequenceMethod::Retrodiction => {
+                self.retrodict(measurement_z, time_s - latest_time_s);
+                true
+            }
+        }
+    }
+
+    /// Time of the current estimate, the latest measurement's time [s];
+    /// None without with_out_of_sequence_updates
+    pub fn get_time_s(&self) -> Option<f64> {
+        self.out_of_sequence
+            .as_ref()
+            .map(|state| state.history.back().unwrap().time_s)
+    }
+
+    // Predict over dt with the discretized model, then correct with the measurement.
+    // Predicts again afterwards, as update does, so the prior just used is never reused.
+    fn step(&mut self, measurement_z: DVector<f64>, dt: f64) {
+        let (A, Q) = (self.out_of_sequence.as_ref().unwrap().discretize)(dt);
+        self.sys_model.st_trns_A = A;
+        self.sys_model.prcs_cvr_Q = Q;
+        self.predict();
+
+        let H = &self.sys_model.st_to_meas_H;
+        self.out_of_sequence.as_mut().unwrap().latest_innovation = Some(Innovation {
+            innovation_y: &measurement_z - H * &self.prd_x,
+            innovation_cvr_S: H * &self.prd_cvr_P * H.transpose() + &self.sys_model.meas_cvr_R,
+            klmn_gain_K: self.klmn_gain_K.clone(),
+        });
+
+        self.correct(measurement_z);
+        self.predict();
+    }
+
+    // The untimed updates would apply measurements at the wrong time and skip the history
+    fn assert_in_sequence_mode(&self, fn_name: &str) {
+        assert!(
+            self.out_of_sequence.is_none(),
+            "KalmanFilter: {} isn't available with with_out_of_sequence_updates, use update_at.",
+            fn_name
+        );
+    }
+
+    // Buffer the current estimate as the latest entry, dropping the oldest when full
+    fn push_history(&mut self, measurement_z: DVector<f64>, time_s: f64) {
+        let state = self.out_of_sequence.as_mut().unwrap();
+        state.history.push_back(HistoryEntry {
+            time_s,
+            measurement_z: Some(measurement_z),
+            est_x: self.est_x.clone(),
+            est_cvr_P: self.est_cvr_P.clone(),
+        });
+
+        // One more than history_len, the oldest entry is the estimate the buffer starts from
+        if state.history.len() > state.history_len + 1 {
+            state.history.pop_front();
+            state.history.front_mut().unwrap().measurement_z = None;
+        }
+    }
+
+    // Roll back to the last entry at or before time_s, then apply the measurement and every
+    // later one in time order
+    fn refilter(&mut self, measurement_z: DVector<f64>, time_s: f64) {
+        let state = self.out_of_sequence.as_mut().unwrap();
+        let insert_idx = state
+            .history
+            .iter()
+            .position(|entry| entry.time_s > time_s)
+            .unwrap();
+        let later_entries: Vec<HistoryEntry> = state.history.drain(insert_idx..).collect();
+
+        let start_entry = state.history.back().unwrap();
+        self.est_x = start_entry.est_x.clone();
+        self.est_cvr_P = start_entry.est_cvr_P.clone();
+
+        let mut prev_time_s = start_entry.time_s;
+        let measurements = std::iter::once((time_s, measurement_z)).chain(
+            later_entries
+                .into_iter()
+                .map(|entry| (entry.time_s, entry.measurement_z.unwrap())),
+        );
+        for (meas_time_s, meas_z) in measurements {
+            self.step(meas_z.clone(), meas_time_s - prev_time_s);
+            self.push_history(meas_z, meas_time_s);
+            prev_time_s = meas_time_s;
+        }
+    }
+
+    // One step retrodiction, dt < 0 back to the measurement time, Bar-Shalom's algorithm A1.
+    // The process noise v over the interval, covariance Q = Q(-dt), is correlated with the
+    // current estimate through the latest update's innovation y, covariance S, and gain K_k:
+    // P_xv = Q - K_k H Q, P_vv = Q - Q H' S^-1 H Q
+    // x_d = A(dt) (x - Q H' S^-1 y), P_d = A(dt) (P + P_vv - P_xv - P_xv') A(dt)'
+    // cov(x, z) = (P - P_xv) A(dt)' H', S_d = H P_d H' + R, K = cov(x, z) S_d^-1
+    // x = x + K (z - H x_d), P = P - K S_d K'
+    fn retrodict(&mut self, measurement_z: DVector<f64>, dt: f64) {
+        let state = self.out_of_sequence.as_mut().unwrap();
+        let (retro_A, _) = (state.discretize)(dt);
+        let (_, prcs_cvr_Q) = (state.discretize)(-dt);
+        let H = &self.sys_model.st_to_meas_H;
+        let latest = state
+            .latest_innovation
+            .as_ref()
+            .expect("retrodict(): No update to retrodict from");
+
+        let noise_gain = &prcs_cvr_Q
+            * H.transpose()
+            * latest
+                .innovation_cvr_S
+                .clone()
+                .try_inverse()
+                .expect("retrodict(): Matrix is not invertible");
+        let noise_cvr_P_vv = &prcs_cvr_Q - &noise_gain * H * &prcs_cvr_Q;
+        let cross_noise_cvr_P_xv = &prcs_cvr_Q - &latest.klmn_gain_K * H * &prcs_cvr_Q;
+
+        let retro_x = &retro_A * (&self.est_x - &noise_gain * &latest.innovation_y);
+        let retro_cvr_P = &retro_A
+            * (&self.est_cvr_P + noise_cvr_P_vv
+                - &cross_noise_cvr_P_xv
+                - cross_noise_cvr_P_xv.transpose())
+            * retro_A.transpose();
+        let cross_cvr_P = (&self.est_cvr_P - &cross_noise_cvr_P_xv) * retro_A.transpose();
+
+        let innovation_cvr = H * &retro_cvr_P * H.transpose() + &self.sys_model.meas_cvr_R;
+        let klmn_gain_K = &cross_cvr_P
+            * H.transpose()
+            * innovation_cvr
+                .clone()
+                .try_inverse()
+                .expect("retrodict(): Matrix is not invertible");
+
+        self.est_x += &klmn_gain_K * (measurement_z - H * retro_x);
+        self.est_cvr_P -= &klmn_gain_K * innovation_cvr * klmn_gain_K.transpose();
+
+        // The latest entry holds the current estimate
+        let latest_entry = state.history.back_mut().unwrap();
+        latest_entry.est_x = self.est_x.clone();
+        latest_entry.est_cvr_P = self.est_cvr_P.clone();
+
+        self.predict();
+    }
+
+    // Steps 3 and 4, with the prediction and Kalman gain from predict()
+    fn correct(&mut self, measurement_z: DVector<f64>) {
         // step 3
         self.est_x = Self::estimate_state(
             measurement_z,
@@ -151,6 +422,42 @@ impl KalmanFilter {
             &self.klmn_gain_K,
             &self.sys_model.st_to_meas_H,
         );
+    }
+
+    /// Replace the state transition matrix, A, for a time-varying system, e.g. one driven by
+    /// gyro rates. The prediction and Kalman gain for the next update are recomputed from the
+    /// current estimate, so call this before each update with that step's A.
+    /// Not available with with_out_of_sequence_updates, where A comes from the discretization.
+    pub fn set_state_transition(&mut self, A: DMatrix<f64>) {
+        // TODO: Input validation to verify matrix dimensions
+        self.assert_in_sequence_mode("set_state_transition");
+
+        self.sys_model.st_trns_A = A;
+        self.predict();
+    }
+
+    /// Restart the filter from a new initial estimate, keeping the system model
+    pub fn reset(&mut self, initial_est_state_x: DVector<f64>, initial_est_covar_P: DMatrix<f64>) {
+        // TODO: Input validation to verify matrix dimensions
+
+        self.est_x = initial_est_state_x;
+        self.est_cvr_P = initial_est_covar_P;
+        self.predict();
+
+        // Timestamped measurements start again from 0 s
+        if let Some(state) = self.out_of_sequence.as_mut() {
+            state.latest_innovation = None;
+            state.history.clear();
+            state.history.push_back(HistoryEntry {
+                time_s: 0.0,
+                measurement_z: None,
+                est_x: self.est_x.clone(),
+                est_cvr_P: self.est_cvr_P.clone(),
+            });
+        }
+    }
+
+    fn predict(&mut self) {
         // step 1.a
         self.prd_x = Self::predict_state(&self.est_x, &self.sys_model.st_trns_A);
         // step 1.b
@@ -178,4 +485,148 @@ impl KalmanFilter {
     pub fn get_kalman_gain(&self) -> DMatrix<f64> {
         self.klmn_gain_K.clone()
     }
+
+    /// Update with each measurement in turn, logging the filter after every step.
+    /// Not available with with_out_of_sequence_updates.
+    pub fn filter_measurements(&mut self, measurements_z: &[DVector<f64>]) -> KalmanHistory {
+        self.assert_in_sequence_mode("filter_measurements");
+
+        let mut history = KalmanHistory {
+            est_xs: Vec::with_capacity(measurements_z.len()),
+            est_cvr_Ps: Vec::with_capacity(measurements_z.len()),
+            klmn_gain_Ks: Vec::with_capacity(measurements_z.len()),
+        };
+
+        for measurement_z in measurements_z {
+            // The gain is calculated in the previous predict step, before the measurement
+            history.klmn_gain_Ks.push(self.klmn_gain_K.clone());
+            self.update(measurement_z.clone());
+            history.est_xs.push(self.est_x.clone());
+            history.est_cvr_Ps.push(self.est_cvr_P.clone());
+        }
+
+        history
+    }
+
+    /// filter_measurements for a single measurement, m = 1
+    pub fn filter_scalar_measurements(&mut self, measurements_z: &[f64]) -> KalmanHistory {
+        let measurements_z: Vec<DVector<f64>> = measurements_z
+            .iter()
+            .map(|&meas_z| DVector::from_element(1, meas_z))
+            .collect();
+        self.filter_measurements(&measurements_z)
+    }
+}
+
+/// Log of a Kalman filter run, one entry per measurement, from
+/// KalmanFilter::filter_measurements
+pub struct KalmanHistory {
+    est_xs: Vec<DVector<f64>>,
+    est_cvr_Ps: Vec<DMatrix<f64>>,
+    klmn_gain_Ks: Vec<DMatrix<f64>>,
+}
+
+impl KalmanHistory {
+    /// State estimate, x, after each measurement
+    pub fn get_state_estimates(&self) -> &[DVector<f64>] {
+        &self.est_xs
+    }
+
+    /// Error covariance estimate, P, after each measurement
+    pub fn get_error_covariances(&self) -> &[DMatrix<f64>] {
+        &self.est_cvr_Ps
+    }
+
+    /// Kalman gain, K, applied to each measurement
+    pub fn get_kalman_gains(&self) -> &[DMatrix<f64>] {
+        &self.klmn_gain_Ks
+    }
+
+    /// One state over time, e.g. for plotting
+    pub fn get_state_series(&self, state_idx: usize) -> Vec<f64> {
+        self.est_xs.iter().map(|est_x| est_x[state_idx]).collect()
+    }
+
+    /// One element of P over time
+    pub fn get_error_covariance_series(&self, row: usize, col: usize) -> Vec<f64> {
+        self.est_cvr_Ps
+            .iter()
+            .map(|est_cvr_P| est_cvr_P[(row, col)])
+            .collect()
+    }
+
+    /// One element of K over time
+    pub fn get_kalman_gain_series(&self, row: usize, col: usize) -> Vec<f64> {
+        self.klmn_gain_Ks
+            .iter()
+            .map(|klmn_gain_K| klmn_gain_K[(row, col)])
+            .collect()
+    }
+}
+
+/// Kalman filter with a single measurement and a single output state, e.g. the voltage in
+/// the Chapter 10 example, usable wherever a recursive Filter is.
+pub struct ScalarKalmanFilter {
+    klmn_filt: KalmanFilter,
+    output_state_idx: usize,
+    initial_est_x: DVector<f64>,
+    initial_est_cvr_P: DMatrix<f64>,
+    initialized: bool,
+}
+
+impl ScalarKalmanFilter {
+    /// system_model: must have a 1 x n observation matrix, H
+    /// output_state_idx: index of the state returned as the filter output
+    pub fn new(
+        system_model: SystemModel,
+        initial_est_state_x: DVector<f64>,
+        initial_est_covar_P: DMatrix<f64>,
+        output_state_idx: usize,
+    ) -> Self {
+        assert!(
+            system_model.st_to_meas_H.nrows() == 1,
+            "ScalarKalmanFilter: Observation matrix, H, must have a single row"
+        );
+        assert!(
+            output_state_idx < initial_est_state_x.len(),
+            "ScalarKalmanFilter: Output state index is out of range"
+        );
+
+        Self {
+            klmn_filt: KalmanFilter::new(
+                system_model,
+                initial_est_state_x.clone(),
+                initial_est_covar_P.clone(),
+            ),
+            output_state_idx,
+            initial_est_x: initial_est_state_x,
+            initial_est_cvr_P: initial_est_covar_P,
+            initialized: false,
+        }
+    }
+
+    pub fn get_kalman_filter(&self) -> &KalmanFilter {
+        &self.klmn_filt
+    }
+}
+
+impl Filter for ScalarKalmanFilter {
+    fn update(&mut self, data: f64) {
+        self.klmn_filt.update(DVector::from_element(1, data));
+        self.initialized = true;
+    }
+
+    fn get_output(&self) -> f64 {
+        self.klmn_filt.est_x[self.output_state_idx]
+    }
+
+    fn reset(&mut self) {
+        self.klmn_filt
+            .reset(self.initial_est_x.clone(), self.initial_est_cvr_P.clone());
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
 }
@@ -1,7 +1,11 @@
-use crate::sensor_spoofs;
-use crate::sensor_spoofs::TRUE_VEL_A;
-use crate::utils::{ascending_float_range, PlotLabels};
-use kalman_filter_for_beginners_rust::kalman_filter::{KalmanFilter, SystemModel};
+use crate::sensor_spoofs::{
+    Latency, PositionSensor, ScheduledSensor, TimestampedReading, VelocitySensor, VoltageSensor,
+};
+use crate::trajectory::TrajectoryPoint;
+use crate::utils::{ascending_float_range, plot_two_lines, PlotLabels};
+use kalman_filter_for_beginners_rust::kalman_filter::{
+    KalmanFilter, OutOfSequenceMethod, SystemModel,
+};
 use nalgebra::{DMatrix, DVector};
 use plotters::prelude::*;
 
@@ -11,10 +15,10 @@ pub fn kalman_filter_extremely_simple_example() {
 
     let num_data_pts: usize = times_s.len();
 
-    let mut measurements_z = Vec::<f64>::with_capacity(num_data_pts);
-    let mut estimates_x = Vec::<f64>::with_capacity(num_data_pts);
-    let mut error_covariance_p = Vec::<f64>::with_capacity(num_data_pts);
-    let mut kalman_gain_k = Vec::<f64>::with_capacity(num_data_pts);
+    let mut volt_sensor = VoltageSensor::from_seed(1);
+    let measurements_z: Vec<f64> = (0..num_data_pts)
+        .map(|_| volt_sensor.measure().measurement)
+        .collect();
 
     // Initialize system model
     let system_model = SystemModel::new(
@@ -31,20 +35,11 @@ pub fn kalman_filter_extremely_simple_example() {
         DMatrix::from_element(1, 1, 6.0),
     );
 
-    // Log initial Kalman gain b/c it's calculated on initialization, before update
-    kalman_gain_k.push(klmn_filt.get_kalman_gain()[0]);
-
     // Run simulation
-    for _ in 0..num_data_pts {
-        let data_pt = sensor_spoofs::get_volt();
-        klmn_filt.update(DVector::from_element(1, data_pt));
-
-        // Log data for plotting
-        measurements_z.push(data_pt);
-        estimates_x.push(klmn_filt.get_state_estimate()[0]);
-        error_covariance_p.push(klmn_filt.get_error_covariance()[0]);
-        kalman_gain_k.push(klmn_filt.get_kalman_gain()[0]);
-    }
+    let history = klmn_filt.filter_scalar_measurements(&measurements_z);
+    let estimates_x = history.get_state_series(0);
+    let error_covariance_p = history.get_error_covariance_series(0, 0);
+    let kalman_gain_k = history.get_kalman_gain_series(0, 0);
 
     // --- MAKE PLOTS --------------------------------------------------------//
     // Build and save graph using plotters crate; graph format based on textbook example
@@ -96,13 +91,12 @@ pub fn kalman_filter_extremely_simple_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &RED,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -113,16 +107,15 @@ pub fn kalman_filter_extremely_simple_example() {
             4, // Size of the points
             &RED,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data1_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -135,13 +128,12 @@ pub fn kalman_filter_extremely_simple_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -152,16 +144,15 @@ pub fn kalman_filter_extremely_simple_example() {
             3, // Size of the points
             &BLUE,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Circle::new((0, 0), 3, BLUE.filled()));
 
@@ -169,8 +160,8 @@ pub fn kalman_filter_extremely_simple_example() {
         .configure_series_labels()
         .position(SeriesLabelPosition::LowerRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -213,7 +204,7 @@ pub fn kalman_filter_extremely_simple_example() {
         .x_label_style(("sans-serif", 18).into_font())
         .y_label_style(("sans-serif", 18).into_font())
         .x_label_formatter(&|x| format!("{}", *x as i64))
-        .y_label_formatter(&|y| format!("{:.1}", *y as f64))
+        .y_label_formatter(&|y| format!("{:.1}", { *y }))
         .draw()
         .expect("configure_mesh() failed");
 
@@ -225,13 +216,12 @@ pub fn kalman_filter_extremely_simple_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -242,16 +232,15 @@ pub fn kalman_filter_extremely_simple_example() {
             3, // Size of the points
             &BLUE,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     let _ = root.present();
 
@@ -295,7 +284,7 @@ pub fn kalman_filter_extremely_simple_example() {
         .x_label_style(("sans-serif", 18).into_font())
         .y_label_style(("sans-serif", 18).into_font())
         .x_label_formatter(&|x| format!("{}", *x as i64))
-        .y_label_formatter(&|y| format!("{:.1}", *y as f64))
+        .y_label_formatter(&|y| format!("{:.1}", { *y }))
         .draw()
         .expect("configure_mesh() failed");
 
@@ -307,13 +296,12 @@ pub fn kalman_filter_extremely_simple_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -324,16 +312,15 @@ pub fn kalman_filter_extremely_simple_example() {
             3, // Size of the points
             &BLUE,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     let _ = root.present();
 
@@ -350,10 +337,13 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
 
     let num_data_pts: usize = times_s.len();
 
-    let mut measurements_z = Vec::<f64>::with_capacity(num_data_pts);
-    let mut pos_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
-    let mut vel_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
-    let mut true_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut position_sensor = PositionSensor::from_seed(1);
+    let (measurements_z, true_vels): (Vec<f64>, Vec<f64>) = (0..num_data_pts)
+        .map(|_| {
+            let reading = position_sensor.measure();
+            (reading.measurement, reading.truth.vel[0])
+        })
+        .unzip();
 
     // Initialize system model
     let a = DMatrix::from_row_slice(2, 2, &[1.0, DT, 0.0, 1.0]);
@@ -371,16 +361,9 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
     );
 
     // Run simulation
-    for _ in 0..num_data_pts {
-        let data_pt = sensor_spoofs::get_position();
-        klmn_filt.update(DVector::from_element(1, data_pt));
-
-        // Log data for plotting
-        measurements_z.push(data_pt);
-        pos_estimates_x.push(klmn_filt.get_state_estimate()[0]);
-        vel_estimates_x.push(klmn_filt.get_state_estimate()[1]);
-        true_vels.push(*TRUE_VEL_A.lock().unwrap());
-    }
+    let history = klmn_filt.filter_scalar_measurements(&measurements_z);
+    let pos_estimates_x = history.get_state_series(0);
+    let vel_estimates_x = history.get_state_series(1);
 
     // --- MAKE PLOTS ----------------------------------------------------//
     // Build and save graph using plotters crate; graph format based on textbook example
@@ -433,16 +416,15 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
             2, // Size of the points
             &RED,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data1_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -455,22 +437,21 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
     chart
         .configure_series_labels()
         .position(SeriesLabelPosition::LowerRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -528,13 +509,12 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &RED,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -545,16 +525,15 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
             4, // Size of the points
             &RED,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data1_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -567,22 +546,21 @@ pub fn kalman_filter_estimate_velocity_from_position_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
     chart
         .configure_series_labels()
         .position(SeriesLabelPosition::LowerRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -598,9 +576,10 @@ pub fn kalman_filter_estimate_position_with_velocity_example() {
 
     let num_data_pts: usize = times_s.len();
 
-    let mut measurements_z = Vec::<f64>::with_capacity(num_data_pts);
-    let mut pos_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
-    let mut vel_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
+    let mut velocity_sensor = VelocitySensor::from_seed(1);
+    let measurements_z: Vec<f64> = (0..num_data_pts)
+        .map(|_| velocity_sensor.measure().measurement)
+        .collect();
 
     // Initialize system model
     let a = DMatrix::from_row_slice(2, 2, &[1.0, DT, 0.0, 1.0]);
@@ -618,15 +597,9 @@ pub fn kalman_filter_estimate_position_with_velocity_example() {
     );
 
     // Run simulation
-    for _ in 0..num_data_pts {
-        let data_pt = sensor_spoofs::get_velocity();
-        klmn_filt.update(DVector::from_element(1, data_pt));
-
-        // Log data for plotting
-        measurements_z.push(data_pt);
-        pos_estimates_x.push(klmn_filt.get_state_estimate()[0]);
-        vel_estimates_x.push(klmn_filt.get_state_estimate()[1]);
-    }
+    let history = klmn_filt.filter_scalar_measurements(&measurements_z);
+    let pos_estimates_x = history.get_state_series(0);
+    let vel_estimates_x = history.get_state_series(1);
 
     // --- MAKE PLOTS ----------------------------------------------------//
     // Build and save graph using plotters crate; graph format based on textbook example
@@ -679,16 +652,15 @@ pub fn kalman_filter_estimate_position_with_velocity_example() {
             2, // Size of the points
             &RED,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data1_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -701,22 +673,21 @@ pub fn kalman_filter_estimate_position_with_velocity_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
     chart
         .configure_series_labels()
         .position(SeriesLabelPosition::UpperRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -773,22 +744,21 @@ pub fn kalman_filter_estimate_position_with_velocity_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
     chart
         .configure_series_labels()
         .position(SeriesLabelPosition::LowerRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -810,9 +780,12 @@ pub fn kalman_filter_measure_velocity_with_sonar_example() {
         let dt: f64 = 0.02; // from example code
         let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
 
-        let mut measurements_z = Vec::<f64>::with_capacity(num_data_pts);
-        let mut pos_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
-        let mut vel_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
+        let measurements_z: Vec<f64> = match sonar_alt_arr.data() {
+            matfile::NumericData::Double { real, .. } => {
+                real.iter().take(num_data_pts).copied().collect()
+            }
+            _ => Vec::new(),
+        };
 
         // Initialize system model
         let a = DMatrix::from_row_slice(2, 2, &[1.0, 0.1, 0.0, 1.0]);
@@ -830,17 +803,8 @@ pub fn kalman_filter_measure_velocity_with_sonar_example() {
         );
 
         // Run simulation
-        if let matfile::NumericData::Double { real, .. } = sonar_alt_arr.data() {
-            for i in 0..num_data_pts {
-                let data_pt = real[i];
-                klmn_filt.update(DVector::from_element(1, data_pt));
-
-                // Log data for plotting
-                measurements_z.push(data_pt);
-                pos_estimates_x.push(klmn_filt.get_state_estimate()[0]);
-                vel_estimates_x.push(klmn_filt.get_state_estimate()[1]);
-            }
-        }
+        let history = klmn_filt.filter_scalar_measurements(&measurements_z);
+        let pos_estimates_x = history.get_state_series(0);
 
         // --- MAKE PLOTS ----------------------------------------------------//
         // Build and save graph using plotters crate; graph format based on textbook example
@@ -893,16 +857,15 @@ pub fn kalman_filter_measure_velocity_with_sonar_example() {
                 2, // Size of the points
                 &RED,
                 &|coord, size, style| {
-                    return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                    EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
                 },
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() PointSeries {} failed",
                     plot_labels.y_axis_data1_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data1_label)
             .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -915,22 +878,21 @@ pub fn kalman_filter_measure_velocity_with_sonar_example() {
                     .map(|(&x_val, &y_val)| (x_val, y_val)),
                 &BLUE,
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() LineSeries {} failed",
                     plot_labels.y_axis_data2_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data2_label)
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
         chart
             .configure_series_labels()
             .position(SeriesLabelPosition::LowerRight)
             .margin(5)
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()
             .expect("configure_series_labels() failed");
 
@@ -939,3 +901,260 @@ pub fn kalman_filter_measure_velocity_with_sonar_example() {
         println!("Kalman filter plot written: {}", plot_labels.plot_pathname);
     }
 }
+
+/// Two position sensors running at different rates over unreliable links: a 10 Hz sensor
+/// that drops samples, has burst outages and random latency, and a 4 Hz sensor with a fixed
+/// 0.3 s latency, so many measurements arrive after later ones. Both watch the same target.
+fn multirate_lossy_position_links() -> (
+    ScheduledSensor<PositionSensor>,
+    ScheduledSensor<PositionSensor>,
+) {
+    let trajectory = PositionSensor::textbook_trajectory(5);
+    let fast_sensor = PositionSensor::from_seed(1).with_trajectory(trajectory.clone());
+    let slow_sensor = PositionSensor::from_seed(2).with_trajectory(trajectory);
+
+    let fast_link = ScheduledSensor::from_seed(fast_sensor, 0.1, 3)
+        .with_dropout(0.1)
+        .with_burst_outages(0.01, 1.0)
+        .with_latency(Latency::Uniform {
+            min_delay_s: 0.05,
+            max_delay_s: 0.35,
+        });
+    let slow_link = ScheduledSensor::from_seed(slow_sensor, 0.25, 4)
+        .with_start_time(0.05)
+        .with_latency(Latency::Fixed { delay_s: 0.3 });
+
+    (fast_link, slow_link)
+}
+
+/// Fuse the two multirate_lossy_position_links sensors. Measurements are applied in arrival
+/// order, each predicted over the time since the last one applied; a measurement older than
+/// that can't be applied, so it's discarded. See kalman_filter_out_of_sequence_example for
+/// applying them instead.
+pub fn kalman_filter_multirate_lossy_sensors_example() {
+    const DURATION_S: f64 = 20.0;
+    const SIM_DT: f64 = 0.05;
+
+    let (mut fast_link, mut slow_link) = multirate_lossy_position_links();
+
+    // Kalman filter set up as in kalman_filter_estimate_velocity_from_position_example, with
+    // the state transition set for the time between measurements
+    let state_transition = |dt: f64| DMatrix::from_row_slice(2, 2, &[1.0, dt, 0.0, 1.0]);
+    let system_model = SystemModel::new(
+        state_transition(0.1),
+        DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]),
+        DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+        DMatrix::from_row_slice(1, 1, &[10.0]),
+    );
+    let mut klmn_filt = KalmanFilter::new(
+        system_model,
+        DVector::from_column_slice(&[0.0, 20.0]),
+        DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+    );
+
+    let mut times_s = Vec::<f64>::new();
+    let mut true_vels = Vec::<f64>::new();
+    let mut vel_estimates = Vec::<f64>::new();
+    let mut latencies_s = Vec::<f64>::new();
+    let mut last_sample_time_s = 0.0;
+    let mut num_stale = 0;
+
+    // Run simulation
+    let num_steps = (DURATION_S / SIM_DT).round() as usize;
+    for step in 1..=num_steps {
+        let time_s = step as f64 * SIM_DT;
+        let mut arrived: Vec<TimestampedReading<f64, TrajectoryPoint>> =
+            fast_link.advance_to(time_s);
+        arrived.extend(slow_link.advance_to(time_s));
+        arrived.sort_by(|a, b| a.arrival_time_s.total_cmp(&b.arrival_time_s));
+
+        for reading in arrived {
+            latencies_s.push(reading.arrival_time_s - reading.sample_time_s);
+            if reading.sample_time_s < last_sample_time_s {
+                num_stale += 1;
+                continue;
+            }
+
+            klmn_filt
+                .set_state_transition(state_transition(reading.sample_time_s - last_sample_time_s));
+            klmn_filt.update(DVector::from_element(1, reading.measurement));
+            last_sample_time_s = reading.sample_time_s;
+
+            // Log data for plotting
+            times_s.push(reading.sample_time_s);
+            true_vels.push(reading.truth.vel[0]);
+            vel_estimates.push(klmn_filt.get_state_estimate()[1]);
+        }
+    }
+
+    for (link_name, num_samples, num_lost) in [
+        (
+            "10 Hz",
+            fast_link.get_num_samples(),
+            fast_link.get_num_lost(),
+        ),
+        (
+            "4 Hz",
+            slow_link.get_num_samples(),
+            slow_link.get_num_lost(),
+        ),
+    ] {
+        println!(
+            "{} position sensor: {} samples, {} lost",
+            link_name, num_samples, num_lost
+        );
+    }
+    let rms_vel_error = (true_vels
+        .iter()
+        .zip(vel_estimates.iter())
+        .map(|(truth, est)| (est - truth).powi(2))
+        .sum::<f64>()
+        / true_vels.len() as f64)
+        .sqrt();
+    println!(
+        "Multi-rate Kalman filter: {} measurements applied, {} out of sequence discarded, \
+         mean latency {:.2} s, RMS velocity error {:.2} m/s",
+        times_s.len(),
+        num_stale,
+        latencies_s.iter().sum::<f64>() / latencies_s.len() as f64,
+        rms_vel_error
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/29_KalmanFilter_MultiRateLossy.png".to_string(),
+        title: "Kalman Filter, Multi-Rate Lossy Sensors".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Speed [m/s]".to_string(),
+        y_axis_data1_label: "True speed".to_string(),
+        y_axis_data2_label: "Kalman Filter".to_string(),
+    };
+
+    plot_two_lines(plot_labels, &times_s, &true_vels, &vel_estimates);
+}
+
+/// Fuse the multirate_lossy_position_links sensors with timestamped updates, comparing the
+/// ways of handling measurements that arrive after later ones: discarding them, re-filtering
+/// from the buffered estimate before them, and one step retrodiction. Re-filtering is exact, so
+/// it has the lowest error. Retrodiction is exact only for measurements taken since the one
+/// before the latest; the slow link's lag often spans several, but it still gets most of
+/// re-filtering's gain over discarding for one update per measurement.
+pub fn kalman_filter_out_of_sequence_example() {
+    const DURATION_S: f64 = 20.0;
+    const SIM_DT: f64 = 0.05;
+    const SETTLING_TIME_S: f64 = 2.0; // errors are compared after the initial transient
+
+    // Every filter sees the same measurements, in the order they arrive
+    let (mut fast_link, mut slow_link) = multirate_lossy_position_links();
+    let mut arrivals = Vec::<TimestampedReading<f64, TrajectoryPoint>>::new();
+    let num_steps = (DURATION_S / SIM_DT).round() as usize;
+    for step in 1..=num_steps {
+        let time_s = step as f64 * SIM_DT;
+        let mut arrived = fast_link.advance_to(time_s);
+        arrived.extend(slow_link.advance_to(time_s));
+        arrived.sort_by(|a, b| a.arrival_time_s.total_cmp(&b.arrival_time_s));
+        arrivals.extend(arrived);
+    }
+
+    // Constant velocity model as in kalman_filter_estimate_velocity_from_position_example,
+    // its Q per 0.1 s step scaled to the time between measurements
+    let discretize = |dt: f64| {
+        (
+            DMatrix::from_row_slice(2, 2, &[1.0, dt, 0.0, 1.0]),
+            DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]) * (dt.abs() / 0.1),
+        )
+    };
+
+    let mut vel_estimates_by_method = Vec::<Vec<f64>>::new();
+    let mut times_s = Vec::<f64>::new();
+    for (method_name, method) in [
+        ("Discard", OutOfSequenceMethod::Discard),
+        ("Re-filtering", OutOfSequenceMethod::Refilter),
+        ("Retrodiction", OutOfSequenceMethod::Retrodiction),
+    ] {
+        let system_model = SystemModel::new(
+            DMatrix::identity(2, 2),
+            DMatrix::zeros(2, 2),
+            DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+            DMatrix::from_row_slice(1, 1, &[10.0]),
+        );
+        let mut klmn_filt = KalmanFilter::new(
+            system_model,
+            DVector::from_column_slice(&[0.0, 20.0]),
+            DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+        )
+        .with_out_of_sequence_updates(discretize, method, 20);
+
+        // Logged after every measurement, at the time of the latest one
+        times_s.clear();
+        let mut vel_estimates = Vec::<f64>::with_capacity(arrivals.len());
+        let mut sum_sq_vel_err = 0.0;
+        let mut num_settled = 0;
+        let mut true_vel = 0.0;
+        let mut num_applied = 0;
+
+        for reading in arrivals.iter() {
+            if reading.sample_time_s >= klmn_filt.get_time_s().unwrap() {
+                true_vel = reading.truth.vel[0];
+            }
+            if klmn_filt.update_at(
+                DVector::from_element(1, reading.measurement),
+                reading.sample_time_s,
+            ) {
+                num_applied += 1;
+            }
+
+            let vel_estimate = klmn_filt.get_state_estimate()[1];
+            let time_s = klmn_filt.get_time_s().unwrap();
+            if time_s >= SETTLING_TIME_S {
+                sum_sq_vel_err += (vel_estimate - true_vel).powi(2);
+                num_settled += 1;
+            }
+            times_s.push(time_s);
+            vel_estimates.push(vel_estimate);
+        }
+
+        println!(
+            "Out of sequence measurements, {}: {} of {} applied, RMS velocity error after {} s {:.2} m/s",
+            method_name,
+            num_applied,
+            arrivals.len(),
+            SETTLING_TIME_S,
+            (sum_sq_vel_err / num_settled as f64).sqrt()
+        );
+        vel_estimates_by_method.push(vel_estimates);
+    }
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/30a_KalmanFilter_OutOfSequenceDiscard.png".to_string(),
+        title: "Kalman Filter, Out of Sequence Measurements".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Speed [m/s]".to_string(),
+        y_axis_data1_label: "Discard".to_string(),
+        y_axis_data2_label: "Re-filtering".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s,
+        &vel_estimates_by_method[0],
+        &vel_estimates_by_method[1],
+    );
+
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/30b_KalmanFilter_OutOfSequenceRetrodiction.png".to_string(),
+        title: "Kalman Filter, Out of Sequence Measurements".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Speed [m/s]".to_string(),
+        y_axis_data1_label: "Retrodiction".to_string(),
+        y_axis_data2_label: "Re-filtering".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s,
+        &vel_estimates_by_method[2],
+        &vel_estimates_by_method[1],
+    );
+}
@@ -1,3 +1,12 @@
+pub mod alpha_beta_filters;
+pub mod attitude_reference_system;
+pub mod biquad_filters;
+pub mod filter_analysis;
+pub mod filter_pipeline;
+pub mod fixed_point_filters;
 pub mod kalman_filter;
 pub mod nonlinear_kalman_filter;
+pub mod outlier_filters;
 pub mod recursive_filters;
+pub mod savitzky_golay_filter;
+pub mod target_tracking;
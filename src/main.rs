@@ -1,27 +1,86 @@
+pub mod alpha_beta_filters_test;
+pub mod attitude_reference_system_test;
+pub mod filter_analysis_test;
 pub mod kalman_filter_test;
+pub mod nonlinear_kalman_filter_test;
 pub mod recursive_filters_test;
+pub mod savitzky_golay_filter_test;
+pub mod sensor_noise;
 pub mod sensor_spoofs;
+pub mod target_tracking_test;
+pub mod trajectory;
 pub mod utils;
 
+use crate::alpha_beta_filters_test::alpha_beta_filter_velocity_from_position_example;
+use crate::attitude_reference_system_test::{
+    attitude_complementary_filter_1st_order_example,
+    attitude_complementary_filter_2nd_order_example, attitude_error_state_kalman_filter_example,
+    attitude_extended_kalman_filter_example, attitude_kalman_filter_example,
+    attitude_simulated_imu_example, attitude_unscented_kalman_filter_example,
+};
+use crate::filter_analysis_test::filter_frequency_response_example;
 use crate::kalman_filter_test::{
     kalman_filter_estimate_position_with_velocity_example,
     kalman_filter_estimate_velocity_from_position_example, kalman_filter_extremely_simple_example,
     kalman_filter_measure_velocity_with_sonar_example,
+    kalman_filter_multirate_lossy_sensors_example, kalman_filter_out_of_sequence_example,
+};
+use crate::nonlinear_kalman_filter_test::{
+    extended_kalman_filter_radar_example, particle_filter_radar_example,
+    unscented_kalman_filter_radar_example,
 };
 use crate::recursive_filters_test::{
-    average_filter_example, first_order_low_pass_filter_example, moving_average_filter_example,
+    average_filter_example, biquad_vibration_filter_example, first_order_high_pass_filter_example,
+    first_order_low_pass_filter_example, fixed_point_low_pass_filter_example,
+    low_pass_filter_variable_rate_example, measurement_noise_estimation_example,
+    moving_average_filter_example, moving_average_warm_up_example,
+    multichannel_low_pass_filter_example, sensor_noise_robustness_example,
+    sonar_dropout_filter_example, sonar_dropout_pipeline_example, sonar_filter_comparison_example,
 };
+use crate::savitzky_golay_filter_test::savitzky_golay_climb_rate_example;
+use crate::target_tracking_test::kalman_filter_image_tracking_example;
 
 fn main() {
     // Recursive Filters
     average_filter_example();
     moving_average_filter_example();
+    moving_average_warm_up_example();
     first_order_low_pass_filter_example();
+    low_pass_filter_variable_rate_example();
+    first_order_high_pass_filter_example();
+    sonar_filter_comparison_example();
+    fixed_point_low_pass_filter_example();
+    multichannel_low_pass_filter_example();
+    biquad_vibration_filter_example();
+    sonar_dropout_filter_example();
+    sonar_dropout_pipeline_example();
+    measurement_noise_estimation_example();
+    sensor_noise_robustness_example();
+    filter_frequency_response_example();
 
     // Kalman Filter
     kalman_filter_extremely_simple_example();
 
     kalman_filter_estimate_velocity_from_position_example();
+    alpha_beta_filter_velocity_from_position_example();
     kalman_filter_estimate_position_with_velocity_example();
     kalman_filter_measure_velocity_with_sonar_example();
+    kalman_filter_multirate_lossy_sensors_example();
+    kalman_filter_out_of_sequence_example();
+    savitzky_golay_climb_rate_example();
+    kalman_filter_image_tracking_example();
+
+    // Nonlinear Kalman Filters
+    extended_kalman_filter_radar_example();
+    unscented_kalman_filter_radar_example();
+    particle_filter_radar_example();
+
+    // Attitude Reference System
+    attitude_kalman_filter_example();
+    attitude_extended_kalman_filter_example();
+    attitude_unscented_kalman_filter_example();
+    attitude_error_state_kalman_filter_example();
+    attitude_complementary_filter_1st_order_example();
+    attitude_complementary_filter_2nd_order_example();
+    attitude_simulated_imu_example();
 }
@@ -0,0 +1,458 @@
+#![allow(non_snake_case)]
+
+use crate::kalman_filter::KalmanFilter;
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, StandardNormal, Uniform};
+
+/// Nonlinear state transition, x_k = f(x_k-1, u_k)
+/// Second argument is the control input, u; empty for systems without one
+pub type StateTransitionFn = Box<dyn Fn(&DVector<f64>, &DVector<f64>) -> DVector<f64>>;
+/// Jacobian of the state transition, A = df/dx, evaluated at (x_k-1, u_k)
+pub type StateTransitionJacobianFn = Box<dyn Fn(&DVector<f64>, &DVector<f64>) -> DMatrix<f64>>;
+/// Nonlinear state to measurement, z_k = h(x_k)
+pub type StateToMeasurementFn = Box<dyn Fn(&DVector<f64>) -> DVector<f64>>;
+/// Jacobian of the state to measurement, H = dh/dx, evaluated at x_k
+pub type StateToMeasurementJacobianFn = Box<dyn Fn(&DVector<f64>) -> DMatrix<f64>>;
+
+/// Based on definition in Chapter 14, pg. 142
+/// State
+/// state_transition_f: StateTransitionFn, // x_k = f(x_k-1, u_k)
+/// Process noise covariance matrix
+/// covariance_mat_state_transition_noise_Q: DMatrix<f64>, // n x n diagonal matrix
+/// Measurement
+/// state_to_measurement_h: StateToMeasurementFn, // z_k = h(x_k)
+/// Measurement noise covariance matrix
+/// covariance_mat_measurement_noise_R: DMatrix<f64>,  // m x m diagonal matrix
+pub struct NonlinearSystemModel {
+    // State
+    st_trns_f: StateTransitionFn,
+    prcs_cvr_Q: DMatrix<f64>,
+    // Measurement
+    st_to_meas_h: StateToMeasurementFn,
+    meas_cvr_R: DMatrix<f64>,
+}
+
+impl NonlinearSystemModel {
+    pub fn new(
+        f: StateTransitionFn,
+        Q: DMatrix<f64>,
+        h: StateToMeasurementFn,
+        R: DMatrix<f64>,
+    ) -> Self {
+        Self {
+            st_trns_f: f,
+            prcs_cvr_Q: Q,
+            st_to_meas_h: h,
+            meas_cvr_R: R,
+        }
+    }
+}
+
+/// Extended Kalman filter, Chapter 14
+/// The linear Kalman filter algorithm with the system matrices A and H replaced by the
+/// Jacobians of f and h, evaluated at the latest estimate and prediction respectively.
+pub struct ExtendedKalmanFilter {
+    sys_model: NonlinearSystemModel,
+    st_trns_jcbn_A: StateTransitionJacobianFn,
+    st_to_meas_jcbn_H: StateToMeasurementJacobianFn,
+    // Prediction
+    prd_x: DVector<f64>,
+    prd_cvr_P: DMatrix<f64>,
+    // Kalman Gain
+    klmn_gain_K: DMatrix<f64>,
+    // Estimation
+    est_x: DVector<f64>,
+    est_cvr_P: DMatrix<f64>,
+}
+
+impl ExtendedKalmanFilter {
+    pub fn new(
+        system_model: NonlinearSystemModel,
+        jacobian_A: StateTransitionJacobianFn,
+        jacobian_H: StateToMeasurementJacobianFn,
+        initial_est_state_x: DVector<f64>,
+        initial_est_covar_P: DMatrix<f64>,
+    ) -> Self {
+        let n = initial_est_state_x.len();
+        let m = system_model.meas_cvr_R.nrows();
+
+        Self {
+            sys_model: system_model,
+            st_trns_jcbn_A: jacobian_A,
+            st_to_meas_jcbn_H: jacobian_H,
+            prd_x: initial_est_state_x.clone(),
+            prd_cvr_P: initial_est_covar_P.clone(),
+            klmn_gain_K: DMatrix::zeros(n, m),
+            est_x: initial_est_state_x,
+            est_cvr_P: initial_est_covar_P,
+        }
+    }
+
+    pub fn update(&mut self, measurement_z: DVector<f64>) {
+        self.update_with_input(&DVector::zeros(0), measurement_z);
+    }
+
+    // All equations based on Figure 14.1
+    // Unlike KalmanFilter, the prediction is made at the start of the update because the
+    // Jacobians, and any control input, are only known once the next sample arrives
+    pub fn update_with_input(&mut self, input_u: &DVector<f64>, measurement_z: DVector<f64>) {
+        // step 1.a
+        let st_trns_A = (self.st_trns_jcbn_A)(&self.est_x, input_u);
+        self.prd_x = (self.sys_model.st_trns_f)(&self.est_x, input_u);
+        // step 1.b
+        self.prd_cvr_P = KalmanFilter::predict_error_covariance(
+            &self.est_cvr_P,
+            &st_trns_A,
+            &self.sys_model.prcs_cvr_Q,
+        );
+        // step 2
+        let st_to_meas_H = (self.st_to_meas_jcbn_H)(&self.prd_x);
+        self.klmn_gain_K = KalmanFilter::calculate_kalman_gain(
+            &self.prd_cvr_P,
+            &st_to_meas_H,
+            &self.sys_model.meas_cvr_R,
+        );
+        // step 3
+        let prd_z = (self.sys_model.st_to_meas_h)(&self.prd_x);
+        self.est_x = &self.prd_x + &self.klmn_gain_K * (measurement_z - prd_z);
+        // step 4
+        self.est_cvr_P = KalmanFilter::estimate_error_convariance(
+            &self.prd_cvr_P,
+            &self.klmn_gain_K,
+            &st_to_meas_H,
+        );
+    }
+
+    pub fn get_state_estimate(&self) -> DVector<f64> {
+        self.est_x.clone()
+    }
+
+    pub fn get_error_covariance(&self) -> DMatrix<f64> {
+        self.est_cvr_P.clone()
+    }
+
+    pub fn get_kalman_gain(&self) -> DMatrix<f64> {
+        self.klmn_gain_K.clone()
+    }
+}
+
+/// Unscented Kalman filter, Chapter 15
+/// Propagates a set of sigma points through f and h instead of linearizing them.
+/// kappa: spread of the sigma points around the mean; n + kappa must be greater than zero
+pub struct UnscentedKalmanFilter {
+    sys_model: NonlinearSystemModel,
+    kappa: f64,
+    // Prediction
+    prd_x: DVector<f64>,
+    prd_cvr_P: DMatrix<f64>,
+    // Kalman Gain
+    klmn_gain_K: DMatrix<f64>,
+    // Estimation
+    est_x: DVector<f64>,
+    est_cvr_P: DMatrix<f64>,
+}
+
+impl UnscentedKalmanFilter {
+    pub fn new(
+        system_model: NonlinearSystemModel,
+        kappa: f64,
+        initial_est_state_x: DVector<f64>,
+        initial_est_covar_P: DMatrix<f64>,
+    ) -> Self {
+        let n = initial_est_state_x.len();
+        let m = system_model.meas_cvr_R.nrows();
+
+        assert!(
+            n as f64 + kappa > 0.0,
+            "UnscentedKalmanFilter: n + kappa must be greater than zero"
+        );
+
+        Self {
+            sys_model: system_model,
+            kappa,
+            prd_x: initial_est_state_x.clone(),
+            prd_cvr_P: initial_est_covar_P.clone(),
+            klmn_gain_K: DMatrix::zeros(n, m),
+            est_x: initial_est_state_x,
+            est_cvr_P: initial_est_covar_P,
+        }
+    }
+
+    // Sigma points and weights, Figure 15.2
+    pub(crate) fn sigma_points(
+        mean_x: &DVector<f64>,
+        cvr_P: &DMatrix<f64>,
+        kappa: f64,
+    ) -> (Vec<DVector<f64>>, Vec<f64>) {
+        let n = mean_x.len();
+        let scale = n as f64 + kappa;
+
+        let sqrt_P = (cvr_P * scale)
+            .cholesky()
+            .expect("sigma_points(): Covariance matrix is not positive definite")
+            .l();
+
+        let mut sigma_X = Vec::with_capacity(2 * n + 1);
+        let mut weights_W = Vec::with_capacity(2 * n + 1);
+
+        sigma_X.push(mean_x.clone());
+        weights_W.push(kappa / scale);
+
+        for k in 0..n {
+            sigma_X.push(mean_x + sqrt_P.column(k));
+            weights_W.push(1.0 / (2.0 * scale));
+        }
+        for k in 0..n {
+            sigma_X.push(mean_x - sqrt_P.column(k));
+            weights_W.push(1.0 / (2.0 * scale));
+        }
+
+        (sigma_X, weights_W)
+    }
+
+    // Unscented transform, Figure 15.3
+    pub(crate) fn unscented_transform(
+        sigma_X: &[DVector<f64>],
+        weights_W: &[f64],
+        noise_cvr: &DMatrix<f64>,
+    ) -> (DVector<f64>, DMatrix<f64>) {
+        let mut mean = DVector::zeros(sigma_X[0].len());
+        for (X, &W) in sigma_X.iter().zip(weights_W.iter()) {
+            mean += W * X;
+        }
+
+        let mut cvr = noise_cvr.clone();
+        for (X, &W) in sigma_X.iter().zip(weights_W.iter()) {
+            let dX = X - &mean;
+            cvr += W * &dX * dX.transpose();
+        }
+
+        (mean, cvr)
+    }
+
+    pub fn update(&mut self, measurement_z: DVector<f64>) {
+        self.update_with_input(&DVector::zeros(0), measurement_z);
+    }
+
+    // All equations based on Figure 15.1
+    pub fn update_with_input(&mut self, input_u: &DVector<f64>, measurement_z: DVector<f64>) {
+        // step 1, sigma points
+        let (sigma_X, weights_W) = Self::sigma_points(&self.est_x, &self.est_cvr_P, self.kappa);
+
+        // step 2, predict state and error covariance
+        let prd_sigma_X: Vec<DVector<f64>> = sigma_X
+            .iter()
+            .map(|X| (self.sys_model.st_trns_f)(X, input_u))
+            .collect();
+        (self.prd_x, self.prd_cvr_P) =
+            Self::unscented_transform(&prd_sigma_X, &weights_W, &self.sys_model.prcs_cvr_Q);
+
+        // step 3, predict measurement and its covariance
+        let prd_sigma_Z: Vec<DVector<f64>> = prd_sigma_X
+            .iter()
+            .map(|X| (self.sys_model.st_to_meas_h)(X))
+            .collect();
+        let (prd_z, prd_cvr_Pz) =
+            Self::unscented_transform(&prd_sigma_Z, &weights_W, &self.sys_model.meas_cvr_R);
+
+        // step 4, Kalman gain
+        let mut cross_cvr_Pxz = DMatrix::zeros(self.prd_x.len(), prd_z.len());
+        for ((X, Z), &W) in prd_sigma_X
+            .iter()
+            .zip(prd_sigma_Z.iter())
+            .zip(weights_W.iter())
+        {
+            cross_cvr_Pxz += W * (X - &self.prd_x) * (Z - &prd_z).transpose();
+        }
+        self.klmn_gain_K = cross_cvr_Pxz
+            * prd_cvr_Pz
+                .clone()
+                .try_inverse()
+                .expect("update_with_input(): Matrix is not invertible");
+
+        // step 5, estimate state and error covariance
+        self.est_x = &self.prd_x + &self.klmn_gain_K * (measurement_z - prd_z);
+        self.est_cvr_P =
+            &self.prd_cvr_P - &self.klmn_gain_K * prd_cvr_Pz * self.klmn_gain_K.transpose();
+    }
+
+    pub fn get_state_estimate(&self) -> DVector<f64> {
+        self.est_x.clone()
+    }
+
+    pub fn get_error_covariance(&self) -> DMatrix<f64> {
+        self.est_cvr_P.clone()
+    }
+
+    pub fn get_kalman_gain(&self) -> DMatrix<f64> {
+        self.klmn_gain_K.clone()
+    }
+}
+
+/// Particle filter, Chapter 16
+/// Represents the state distribution with weighted samples. Process noise is drawn from Q and
+/// each particle is weighted by the Gaussian likelihood of the measurement given R.
+/// The particles are resampled, systematically, after every update.
+/// R: random number generator for the particles, StdRng by default; reusing a seed
+/// reproduces a run.
+pub struct ParticleFilter<R: Rng = StdRng> {
+    sys_model: NonlinearSystemModel,
+    sqrt_prcs_cvr_Q: DMatrix<f64>,
+    inv_meas_cvr_R: DMatrix<f64>,
+    particles: Vec<DVector<f64>>,
+    weights: Vec<f64>,
+    rng: R,
+    // Estimation
+    est_x: DVector<f64>,
+    est_cvr_P: DMatrix<f64>,
+}
+
+impl ParticleFilter {
+    pub fn from_seed(
+        system_model: NonlinearSystemModel,
+        num_particles: usize,
+        initial_est_state_x: DVector<f64>,
+        initial_est_covar_P: DMatrix<f64>,
+        seed: u64,
+    ) -> Self {
+        Self::new(
+            system_model,
+            num_particles,
+            initial_est_state_x,
+            initial_est_covar_P,
+            StdRng::seed_from_u64(seed),
+        )
+    }
+}
+
+impl<R: Rng> ParticleFilter<R> {
+    pub fn new(
+        system_model: NonlinearSystemModel,
+        num_particles: usize,
+        initial_est_state_x: DVector<f64>,
+        initial_est_covar_P: DMatrix<f64>,
+        mut rng: R,
+    ) -> Self {
+        assert!(
+            num_particles > 0,
+            "ParticleFilter: Number of particles must be greater than zero."
+        );
+
+        let sqrt_P = Self::matrix_sqrt(&initial_est_covar_P);
+        let particles: Vec<DVector<f64>> = (0..num_particles)
+            .map(|_| {
+                &initial_est_state_x + &sqrt_P * Self::standard_normal(sqrt_P.ncols(), &mut rng)
+            })
+            .collect();
+
+        let sqrt_prcs_cvr_Q = Self::matrix_sqrt(&system_model.prcs_cvr_Q);
+        let inv_meas_cvr_R = system_model
+            .meas_cvr_R
+            .clone()
+            .try_inverse()
+            .expect("ParticleFilter: Measurement noise covariance matrix is not invertible");
+
+        Self {
+            sys_model: system_model,
+            sqrt_prcs_cvr_Q,
+            inv_meas_cvr_R,
+            particles,
+            weights: vec![1.0 / num_particles as f64; num_particles],
+            rng,
+            est_x: initial_est_state_x,
+            est_cvr_P: initial_est_covar_P,
+        }
+    }
+
+    // Matrix square root, S * S' = cvr; unlike Cholesky, allows zero variance (noise free) states
+    fn matrix_sqrt(cvr: &DMatrix<f64>) -> DMatrix<f64> {
+        let sym_eig = cvr.clone().symmetric_eigen();
+        let sqrt_eigenvalues = sym_eig.eigenvalues.map(|e| e.max(0.0).sqrt());
+        sym_eig.eigenvectors * DMatrix::from_diagonal(&sqrt_eigenvalues)
+    }
+
+    fn standard_normal(n: usize, rng: &mut R) -> DVector<f64> {
+        DVector::from_fn(n, |_, _| StandardNormal.sample(rng))
+    }
+
+    pub fn update(&mut self, measurement_z: DVector<f64>) {
+        self.update_with_input(&DVector::zeros(0), measurement_z);
+    }
+
+    // All equations based on Figure 16.1
+    pub fn update_with_input(&mut self, input_u: &DVector<f64>, measurement_z: DVector<f64>) {
+        // step 1, propagate particles through the system model with process noise
+        for particle in self.particles.iter_mut() {
+            let noise = Self::standard_normal(self.sqrt_prcs_cvr_Q.ncols(), &mut self.rng);
+            *particle =
+                (self.sys_model.st_trns_f)(particle, input_u) + &self.sqrt_prcs_cvr_Q * noise;
+        }
+
+        // step 2, weight particles by the measurement likelihood
+        for (particle, weight) in self.particles.iter().zip(self.weights.iter_mut()) {
+            let residual = &measurement_z - (self.sys_model.st_to_meas_h)(particle);
+            let mahalanobis_sq = (residual.transpose() * &self.inv_meas_cvr_R * &residual)[0];
+            *weight *= (-0.5 * mahalanobis_sq).exp();
+        }
+
+        let weight_sum: f64 = self.weights.iter().sum();
+        if weight_sum > 0.0 && weight_sum.is_finite() {
+            self.weights.iter_mut().for_each(|w| *w /= weight_sum);
+        } else {
+            // Every particle is too far from the measurement; fall back to equal weights
+            let num_particles = self.weights.len();
+            self.weights.fill(1.0 / num_particles as f64);
+        }
+
+        // step 3, estimate state and error covariance from the weighted particles
+        let mut est_x = DVector::zeros(self.est_x.len());
+        for (particle, &weight) in self.particles.iter().zip(self.weights.iter()) {
+            est_x += weight * particle;
+        }
+        let mut est_cvr_P = DMatrix::zeros(self.est_x.len(), self.est_x.len());
+        for (particle, &weight) in self.particles.iter().zip(self.weights.iter()) {
+            let dx = particle - &est_x;
+            est_cvr_P += weight * &dx * dx.transpose();
+        }
+        self.est_x = est_x;
+        self.est_cvr_P = est_cvr_P;
+
+        // step 4, resample
+        self.systematic_resample();
+    }
+
+    fn systematic_resample(&mut self) {
+        let num_particles = self.particles.len();
+        let step = 1.0 / num_particles as f64;
+        let start: f64 = Uniform::new(0.0, step).sample(&mut self.rng);
+
+        let mut resampled = Vec::with_capacity(num_particles);
+        let mut cumulative_weight = self.weights[0];
+        let mut idx = 0;
+        for k in 0..num_particles {
+            let threshold = start + k as f64 * step;
+            while cumulative_weight < threshold && idx < num_particles - 1 {
+                idx += 1;
+                cumulative_weight += self.weights[idx];
+            }
+            resampled.push(self.particles[idx].clone());
+        }
+
+        self.particles = resampled;
+        self.weights.fill(step);
+    }
+
+    pub fn get_state_estimate(&self) -> DVector<f64> {
+        self.est_x.clone()
+    }
+
+    pub fn get_error_covariance(&self) -> DMatrix<f64> {
+        self.est_cvr_P.clone()
+    }
+
+    pub fn get_particles(&self) -> &[DVector<f64>] {
+        &self.particles
+    }
+}
@@ -0,0 +1,175 @@
+use crate::sensor_spoofs::RadarSensor;
+use crate::utils::{ascending_float_range, plot_two_lines, PlotLabels};
+use kalman_filter_for_beginners_rust::nonlinear_kalman_filter::{
+    ExtendedKalmanFilter, NonlinearSystemModel, ParticleFilter, UnscentedKalmanFilter,
+};
+use nalgebra::{DMatrix, DVector};
+
+// Radar tracking inputs based on textbook example
+const RADAR_DT: f64 = 0.05;
+const RADAR_END_TIME_S: f64 = 20.0;
+
+/// Aircraft state x = [horizontal position, horizontal speed, altitude] moving at constant
+/// speed and altitude; the radar measures slant range, sqrt(pos^2 + alt^2)
+fn radar_system_model(q: DMatrix<f64>, r: DMatrix<f64>) -> NonlinearSystemModel {
+    let f = Box::new(|x: &DVector<f64>, _u: &DVector<f64>| radar_state_transition_a() * x);
+    let h =
+        Box::new(|x: &DVector<f64>| DVector::from_element(1, (x[0].powi(2) + x[2].powi(2)).sqrt()));
+
+    NonlinearSystemModel::new(f, q, h, r)
+}
+
+fn radar_state_transition_a() -> DMatrix<f64> {
+    DMatrix::from_row_slice(3, 3, &[1.0, RADAR_DT, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+}
+
+/// Run one filter over the simulated radar flight and plot its estimates against the truth.
+/// filter_update takes a range measurement and returns the new state estimate.
+fn radar_tracking_example(
+    filter_name: &str,
+    plot_pathname_prefix: &str,
+    mut filter_update: impl FnMut(f64) -> DVector<f64>,
+) {
+    // Setup simulation & data logging
+    let times_s: Vec<f64> = ascending_float_range(0.0, RADAR_END_TIME_S, RADAR_DT);
+
+    let num_data_pts: usize = times_s.len();
+
+    let mut pos_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
+    let mut vel_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
+    let mut alt_estimates_x = Vec::<f64>::with_capacity(num_data_pts);
+    let mut true_poss = Vec::<f64>::with_capacity(num_data_pts);
+    let mut true_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut true_alts = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Every filter tracks the same flight, with the same measurement noise
+    let mut radar = RadarSensor::from_seed(1);
+
+    // Run simulation
+    for _ in 0..num_data_pts {
+        let reading = radar.measure(RADAR_DT);
+        let est_x = filter_update(reading.measurement);
+
+        // Log data for plotting
+        pos_estimates_x.push(est_x[0]);
+        vel_estimates_x.push(est_x[1]);
+        alt_estimates_x.push(est_x[2]);
+        true_poss.push(reading.truth.pos[0]);
+        true_vels.push(reading.truth.vel[0]);
+        true_alts.push(reading.truth.pos[1]);
+    }
+
+    // Root mean square error of each state, for comparing filters on the same problem
+    let rms_error = |truth: &[f64], estimates: &[f64]| -> f64 {
+        let sum_sq: f64 = truth
+            .iter()
+            .zip(estimates.iter())
+            .map(|(t, e)| (t - e).powi(2))
+            .sum();
+        (sum_sq / truth.len() as f64).sqrt()
+    };
+    println!(
+        "{} radar tracking RMS error: position {:.2} m, speed {:.2} m/s, altitude {:.2} m",
+        filter_name,
+        rms_error(&true_poss, &pos_estimates_x),
+        rms_error(&true_vels, &vel_estimates_x),
+        rms_error(&true_alts, &alt_estimates_x),
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let states = [
+        ("a", "Position [m]", &true_poss, &pos_estimates_x),
+        ("b", "Speed [m/s]", &true_vels, &vel_estimates_x),
+        ("c", "Altitude [m]", &true_alts, &alt_estimates_x),
+    ];
+
+    for (plot_suffix, y_axis_label, truth, estimates) in states {
+        let plot_labels = PlotLabels {
+            plot_pathname: format!(
+                "./plots/{}{}_{}_Radar.png",
+                plot_pathname_prefix,
+                plot_suffix,
+                filter_name.replace(' ', "")
+            ),
+            title: filter_name.to_string(),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: y_axis_label.to_string(),
+            y_axis_data1_label: "True".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(plot_labels, &times_s, truth, estimates);
+    }
+}
+
+pub fn extended_kalman_filter_radar_example() {
+    // Initialize system model
+    let q = DMatrix::from_diagonal(&DVector::from_column_slice(&[0.0, 0.001, 0.001]));
+    let r = DMatrix::from_element(1, 1, 10.0);
+
+    let system_model = radar_system_model(q, r);
+
+    // Jacobians of the state transition and the slant range measurement
+    let jacobian_a = Box::new(|_x: &DVector<f64>, _u: &DVector<f64>| radar_state_transition_a());
+    let jacobian_h = Box::new(|x: &DVector<f64>| {
+        let range = (x[0].powi(2) + x[2].powi(2)).sqrt();
+        DMatrix::from_row_slice(1, 3, &[x[0] / range, 0.0, x[2] / range])
+    });
+
+    // Initialize extended Kalman filter
+    let mut ekf = ExtendedKalmanFilter::new(
+        system_model,
+        jacobian_a,
+        jacobian_h,
+        DVector::from_column_slice(&[0.0, 90.0, 1100.0]),
+        DMatrix::identity(3, 3) * 10.0,
+    );
+
+    radar_tracking_example("Extended Kalman Filter", "06", |data_pt| {
+        ekf.update(DVector::from_element(1, data_pt));
+        ekf.get_state_estimate()
+    });
+}
+
+pub fn unscented_kalman_filter_radar_example() {
+    // Initialize system model
+    let q = DMatrix::identity(3, 3) * 0.01;
+    let r = DMatrix::from_element(1, 1, 100.0);
+
+    let system_model = radar_system_model(q, r);
+
+    // Initialize unscented Kalman filter, kappa = 0
+    let mut ukf = UnscentedKalmanFilter::new(
+        system_model,
+        0.0,
+        DVector::from_column_slice(&[0.0, 90.0, 1100.0]),
+        DMatrix::identity(3, 3) * 100.0,
+    );
+
+    radar_tracking_example("Unscented Kalman Filter", "07", |data_pt| {
+        ukf.update(DVector::from_element(1, data_pt));
+        ukf.get_state_estimate()
+    });
+}
+
+pub fn particle_filter_radar_example() {
+    // Initialize system model
+    let q = DMatrix::identity(3, 3);
+    let r = DMatrix::from_element(1, 1, 100.0);
+
+    let system_model = radar_system_model(q, r);
+
+    // Initialize particle filter, 1000 particles, seeded so the run is repeatable
+    let mut pf = ParticleFilter::from_seed(
+        system_model,
+        1000,
+        DVector::from_column_slice(&[0.0, 90.0, 1100.0]),
+        DMatrix::identity(3, 3) * 100.0,
+        1,
+    );
+
+    radar_tracking_example("Particle Filter", "08", |data_pt| {
+        pf.update(DVector::from_element(1, data_pt));
+        pf.get_state_estimate()
+    });
+}
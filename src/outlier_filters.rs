@@ -0,0 +1,174 @@
+use crate::recursive_filters::{Filter, RingBuffer};
+use num_traits::Float;
+
+/// Scale from the median absolute deviation to the standard deviation of Gaussian data
+const MAD_TO_STD_DEV: f64 = 1.4826;
+
+/// Sliding window median. Unlike a moving average, a single outlier in the window doesn't
+/// move the output, so impulsive noise, e.g. sonar dropouts, is removed rather than smeared.
+/// Like MovingAverageFilter, the buffer is initialized with the first data value. NaN data
+/// points, e.g. dropped sensor samples, are left out of the median.
+/// T: floating point sample type, f32 or f64
+pub struct MedianFilter<T: Float = f64> {
+    median: T,
+    window_size: usize,
+    data_buff: Option<RingBuffer<T>>,
+}
+
+impl<T: Float> MedianFilter<T> {
+    /// window_size: number of data points in the window, odd sizes give a true data point
+    /// as the median, even sizes the mean of the two middle data points
+    pub fn new(window_size: usize) -> Self {
+        assert!(
+            window_size > 0,
+            "MedianFilter: Window size must be greater than zero."
+        );
+
+        Self {
+            median: T::zero(),
+            window_size,
+            data_buff: None,
+        }
+    }
+
+    pub fn update(&mut self, data: T) {
+        if let Some(ref mut data_buff) = self.data_buff {
+            data_buff.push(data);
+            self.median = median(data_buff.as_slice());
+        } else {
+            self.data_buff = Some(RingBuffer::filled(data, self.window_size));
+            self.median = data;
+        }
+    }
+
+    pub fn get_median(&self) -> T {
+        self.median
+    }
+}
+
+impl<T: Float> Filter<T> for MedianFilter<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_median()
+    }
+
+    fn reset(&mut self) {
+        self.median = T::zero();
+        self.data_buff = None;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// Hampel identifier, a sliding window outlier filter.
+/// A data point more than k scaled median absolute deviations (MADs) from the window median
+/// is replaced by the median; other data points pass through unchanged.
+/// sigma = 1.4826 * median(|x_i - median(x)|), outlier if |x - median(x)| > k * sigma
+/// The window holds the raw data, including replaced outliers, so the filter is causal and
+/// judges each data point against itself and the window_size - 1 before it; until the window
+/// fills, against the data points so far. When the MAD is zero, e.g. a flat or quantized
+/// signal, any deviation from the median is an outlier, as in MATLAB's hampel. A NaN data
+/// point, e.g. a dropped sensor sample, is always an outlier.
+/// T: floating point sample type, f32 or f64
+pub struct HampelFilter<T: Float = f64> {
+    output: T,
+    num_mads: T, // 'k' in formula
+    window_size: usize,
+    data_buff: Option<RingBuffer<T>>,
+    outlier: bool,
+}
+
+impl<T: Float> HampelFilter<T> {
+    /// window_size: number of data points in the window
+    /// num_mads: k, threshold in scaled MADs, 3 is the usual choice
+    pub fn new(window_size: usize, num_mads: T) -> Self {
+        assert!(
+            window_size > 0,
+            "HampelFilter: Window size must be greater than zero."
+        );
+        assert!(
+            num_mads > T::zero(),
+            "HampelFilter: Threshold, num_mads, must be greater than zero."
+        );
+
+        Self {
+            output: T::zero(),
+            num_mads,
+            window_size,
+            data_buff: None,
+            outlier: false,
+        }
+    }
+
+    pub fn update(&mut self, data: T) {
+        let data_buff = self
+            .data_buff
+            .get_or_insert_with(|| RingBuffer::empty(self.window_size));
+        data_buff.push(data);
+
+        let window = data_buff.as_slice();
+        let window_median = median(window);
+        let abs_deviations: Vec<T> = window.iter().map(|&x| (x - window_median).abs()).collect();
+        let sigma = T::from(MAD_TO_STD_DEV).unwrap() * median(&abs_deviations);
+
+        let deviation = (data - window_median).abs();
+        self.outlier = data.is_nan()
+            || if sigma > T::zero() {
+                deviation > self.num_mads * sigma
+            } else {
+                deviation > T::zero()
+            };
+        self.output = if self.outlier { window_median } else { data };
+    }
+
+    pub fn get_output(&self) -> T {
+        self.output
+    }
+
+    /// True if the latest data point was replaced by the window median
+    pub fn is_outlier(&self) -> bool {
+        self.outlier
+    }
+}
+
+impl<T: Float> Filter<T> for HampelFilter<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_output()
+    }
+
+    fn reset(&mut self) {
+        self.output = T::zero();
+        self.data_buff = None;
+        self.outlier = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// Median of a non-empty slice; the mean of the two middle values for even lengths.
+/// NaN values are skipped, NaN if every value is NaN.
+fn median<T: Float>(data: &[T]) -> T {
+    let mut sorted: Vec<T> = data.iter().copied().filter(|x| !x.is_nan()).collect();
+    if sorted.is_empty() {
+        return T::nan();
+    }
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        sorted[mid]
+    } else {
+        (sorted[mid - 1] + sorted[mid]) / T::from(2.0).unwrap()
+    }
+}
@@ -1,123 +1,876 @@
-pub struct AverageFilter {
-    avg: f64,
+use nalgebra::DVector;
+use num_traits::Float;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+/// Common interface of the single channel recursive filters, so a data series can be run
+/// through any of them with the same code.
+/// T: sample type, f64 unless stated otherwise
+pub trait Filter<T = f64> {
+    /// Add the newest data point
+    fn update(&mut self, data: T);
+    /// Current filter output, e.g. the average
+    fn get_output(&self) -> T;
+    /// Forget all data, as if newly constructed
+    fn reset(&mut self);
+    /// True once the filter has received data since construction or the last reset
+    fn is_initialized(&self) -> bool;
+}
+
+/// Run a whole data series through a filter, returning the output after each data point.
+/// The filter keeps its state, so a later call carries on from the end of this series.
+pub fn filter_series<T: Clone, F: Filter<T> + ?Sized>(filter: &mut F, data: &[T]) -> Vec<T> {
+    data.iter()
+        .map(|data_pt| {
+            filter.update(data_pt.clone());
+            filter.get_output()
+        })
+        .collect()
+}
+
+/// Iterator adapter that yields a filter's output after each data point, see
+/// FilterIterator::filtered
+pub struct Filtered<I, F, T> {
+    iter: I,
+    filter: F,
+    sample_type: PhantomData<T>,
+}
+
+impl<I, F, T> Filtered<I, F, T> {
+    /// The filter, with the state left by the data points consumed so far
+    pub fn get_filter(&self) -> &F {
+        &self.filter
+    }
+}
+
+impl<I, F, T> Iterator for Filtered<I, F, T>
+where
+    I: Iterator,
+    I::Item: Borrow<T>,
+    F: Filter<T>,
+    T: Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let data_pt = self.iter.next()?;
+        self.filter.update(data_pt.borrow().clone());
+        Some(self.filter.get_output())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Filter any iterator of data points, by value or by reference, e.g.
+/// let smoothed: Vec<f64> = data.iter().filtered(LowPassFilter1stOrder::new(0.7)).collect();
+pub trait FilterIterator: Iterator + Sized {
+    fn filtered<T, F>(self, filter: F) -> Filtered<Self, F, T>
+    where
+        Self::Item: Borrow<T>,
+        F: Filter<T>,
+    {
+        Filtered {
+            iter: self,
+            filter,
+            sample_type: PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator> FilterIterator for I {}
+
+/// Also tracks the variance of the data, using Welford's algorithm:
+/// M2_k = M2_k-1 + (x_k - avg_k-1) * (x_k - avg_k), variance = M2_k / (k - 1)
+/// T: floating point sample type, f32 or f64
+#[derive(Default)]
+pub struct AverageFilter<T: Float = f64> {
+    avg: T,
+    sum_sq_dev: T, // 'M2' in formula, sum of squared deviations from the average
     total_data_pts: usize, // 'k' in formula
 }
 
-impl AverageFilter {
+impl<T: Float> AverageFilter<T> {
     pub fn new() -> Self {
         Self {
-            avg: 0.0,
+            avg: T::zero(),
+            sum_sq_dev: T::zero(),
             total_data_pts: 0,
         }
     }
 
-    pub fn update(&mut self, data: f64) {
+    pub fn update(&mut self, data: T) {
         self.total_data_pts += 1;
 
-        let alpha = 1.0 - 1.0 / self.total_data_pts as f64;
-        self.avg = alpha * self.avg + (1.0 - alpha) * data;
+        let prev_avg = self.avg;
+        let alpha = T::one() - T::one() / T::from(self.total_data_pts).unwrap();
+        self.avg = alpha * self.avg + (T::one() - alpha) * data;
+        self.sum_sq_dev = self.sum_sq_dev + (data - prev_avg) * (data - self.avg);
     }
 
-    pub fn get_average(&self) -> f64 {
+    pub fn get_average(&self) -> T {
         self.avg
     }
+
+    /// Sample variance of all data so far, zero until there are two data points.
+    /// From stationary sensor data this is the measurement noise variance, R.
+    pub fn get_variance(&self) -> T {
+        if self.total_data_pts < 2 {
+            return T::zero();
+        }
+
+        self.sum_sq_dev / T::from(self.total_data_pts - 1).unwrap()
+    }
+
+    pub fn get_std_dev(&self) -> T {
+        self.get_variance().sqrt()
+    }
+}
+
+impl<T: Float> Filter<T> for AverageFilter<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_average()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.total_data_pts > 0
+    }
+}
+
+/// How MovingAverageFilter fills its window before it has seen window_size data points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarmUp<T = f64> {
+    /// Fill the window with copies of the first data point; the output starts at the first
+    /// data point but is biased toward it for the first window_size data points
+    PrefillFirstSample,
+    /// Average only the data points seen so far, until the window is full
+    GrowWindow,
+    /// Fill the window with copies of a given value, e.g. a known nominal reading
+    PrefillValue(T),
 }
 
-pub struct MovingAverageFilter {
-    avg: f64,
+/// Also tracks the variance, min and max of the data in the window. The variance uses a
+/// sliding form of Welford's algorithm, replacing the oldest data point x_o with the newest x_n:
+/// M2_k = M2_k-1 + (x_n - x_o) * (x_n - avg_k + x_o - avg_k-1), variance = M2_k / (n - 1)
+/// T: floating point sample type, f32 or f64
+pub struct MovingAverageFilter<T: Float = f64> {
+    avg: T,
+    sum_sq_dev: T,      // 'M2' in formula, sum of squared deviations from the average
     window_size: usize, // 'n' in formula
-    data_buff: Option<Vec<f64>>,
-    oldest_data_idx: usize,
+    warm_up: WarmUp<T>,
+    data_buff: Option<RingBuffer<T>>,
+    num_data_pts: usize, // data points seen, up to window_size
 }
 
-impl MovingAverageFilter {
+impl<T: Float> MovingAverageFilter<T> {
+    /// Window prefilled with the first data point, see WarmUp::PrefillFirstSample
     pub fn new(window_size: usize) -> Self {
+        Self::with_warm_up(window_size, WarmUp::PrefillFirstSample)
+    }
+
+    pub fn with_warm_up(window_size: usize, warm_up: WarmUp<T>) -> Self {
         assert!(
             window_size > 0,
             "MovingAverageFilter: Window size must be greater than zero."
         );
 
         Self {
-            avg: 0.0,
+            avg: T::zero(),
+            sum_sq_dev: T::zero(),
             window_size,
+            warm_up,
             data_buff: None,
-            oldest_data_idx: 0,
+            num_data_pts: 0,
         }
     }
 
-    pub fn update(&mut self, data: f64) {
-        if let Some(ref mut data_buff) = self.data_buff {
-            // Calculate rolling average
-            let oldest_data = data_buff[self.oldest_data_idx];
-            self.avg = self.avg + (data - oldest_data) / self.window_size as f64;
+    pub fn update(&mut self, data: T) {
+        if self.data_buff.is_none() {
+            // Inititially there are not enough data points for rolling average
+            // The warm up policy sets how the buffer starts out
+            let (data_buff, avg) = match self.warm_up {
+                WarmUp::PrefillFirstSample => (RingBuffer::filled(data, self.window_size), data),
+                WarmUp::GrowWindow => (RingBuffer::empty(self.window_size), T::zero()),
+                WarmUp::PrefillValue(value) => (RingBuffer::filled(value, self.window_size), value),
+            };
+            self.data_buff = Some(data_buff);
+            self.avg = avg;
+            self.sum_sq_dev = T::zero();
+        }
+
+        let data_buff = self.data_buff.as_mut().unwrap();
+        let prev_avg = self.avg;
+        self.num_data_pts = (self.num_data_pts + 1).min(self.window_size);
 
-            // Book keeping to remove oldest and add newest data point to buffer
-            data_buff[self.oldest_data_idx] = data;
-            self.oldest_data_idx = (self.oldest_data_idx + 1) % self.window_size;
+        if let Some(oldest_data) = data_buff.push(data) {
+            // Remove oldest and add newest data point to buffer, then
+            // calculate rolling average
+            self.avg = self.avg + (data - oldest_data) / T::from(self.window_size).unwrap();
+
+            // Rounding can leave a tiny negative sum for constant data
+            let sum_sq_dev =
+                self.sum_sq_dev + (data - oldest_data) * (data - self.avg + oldest_data - prev_avg);
+            self.sum_sq_dev = sum_sq_dev.max(T::zero());
         } else {
-            // Inititially there are not enough data points for rolling average
-            // This implementation initializes the buffer with the first data value
-            self.data_buff = Some(vec![data; self.window_size]);
-            self.avg = data;
+            // Window still growing, cumulative average as in AverageFilter
+            let num_buff_pts = T::from(data_buff.len()).unwrap();
+            self.avg = self.avg + (data - self.avg) / num_buff_pts;
+            self.sum_sq_dev = self.sum_sq_dev + (data - prev_avg) * (data - self.avg);
         }
     }
 
-    pub fn get_average(&self) -> f64 {
+    /// True once window_size data points have been seen, whatever the warm up policy
+    pub fn is_full(&self) -> bool {
+        self.num_data_pts == self.window_size
+    }
+
+    pub fn get_average(&self) -> T {
         self.avg
     }
+
+    /// Sample variance of the data in the window, zero for fewer than two data points
+    pub fn get_variance(&self) -> T {
+        let num_buff_pts = self
+            .data_buff
+            .as_ref()
+            .map_or(0, |data_buff| data_buff.len());
+        if num_buff_pts < 2 {
+            return T::zero();
+        }
+
+        self.sum_sq_dev / T::from(num_buff_pts - 1).unwrap()
+    }
+
+    pub fn get_std_dev(&self) -> T {
+        self.get_variance().sqrt()
+    }
+
+    /// Smallest data point in the window, zero before the first update
+    pub fn get_min(&self) -> T {
+        self.data_buff.as_ref().map_or(T::zero(), |data_buff| {
+            data_buff
+                .as_slice()
+                .iter()
+                .fold(T::infinity(), |min, &x| min.min(x))
+        })
+    }
+
+    /// Largest data point in the window, zero before the first update
+    pub fn get_max(&self) -> T {
+        self.data_buff.as_ref().map_or(T::zero(), |data_buff| {
+            data_buff
+                .as_slice()
+                .iter()
+                .fold(T::neg_infinity(), |max, &x| max.max(x))
+        })
+    }
 }
 
-pub struct LowPassFilter1stOrder {
-    avg: f64,
-    alpha: f64,
+impl<T: Float> Filter<T> for MovingAverageFilter<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_average()
+    }
+
+    fn reset(&mut self) {
+        self.avg = T::zero();
+        self.sum_sq_dev = T::zero();
+        self.data_buff = None;
+        self.num_data_pts = 0;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// avg = alpha * avg + (1 - alpha) * data, see exponential_smoothing
+/// T: floating point sample type, f32 or f64
+pub struct LowPassFilter1stOrder<T: Float = f64> {
+    avg: T,
+    alpha: T,
+    time_constant_s: Option<T>, // tau, known unless constructed from alpha alone
     initialized: bool,
 }
 
-impl LowPassFilter1stOrder {
-    pub fn new(alpha: f64) -> Self {
+impl<T: Float> LowPassFilter1stOrder<T> {
+    /// alpha: smoothing factor, 0.0 < alpha < 1.0, a larger alpha smooths more
+    pub fn new(alpha: T) -> Self {
         assert!(
-            (alpha > 0.0) && (alpha < 1.0),
+            (alpha > T::zero()) && (alpha < T::one()),
             "LowPassFilter1stOrder: Smoothing factor, alpha,
              must be between zero and one, 0.0 < alpha < 1.0"
         );
 
         Self {
-            avg: 0.0,
+            avg: T::zero(),
             alpha,
+            time_constant_s: None,
             initialized: false,
         }
     }
 
-    pub fn update(&mut self, data: f64) {
+    /// time_constant_s: tau, time for the output to reach 63% of a step in the data [s]
+    /// dt: sample period used by update() [s]
+    /// alpha = tau / (tau + dt)
+    pub fn from_time_constant(time_constant_s: T, dt: T) -> Self {
+        assert!(
+            (time_constant_s > T::zero()) && (dt > T::zero()),
+            "LowPassFilter1stOrder: Time constant and sample period must be greater than zero."
+        );
+
+        let mut lpf = Self::new(smoothing_factor(time_constant_s, dt));
+        lpf.time_constant_s = Some(time_constant_s);
+        lpf
+    }
+
+    /// cutoff_freq_hz: -3 dB frequency, above this the data is attenuated
+    /// dt: sample period used by update() [s]
+    /// tau = 1 / (2 pi cutoff_freq_hz)
+    pub fn from_cutoff_frequency(cutoff_freq_hz: T, dt: T) -> Self {
+        assert!(
+            cutoff_freq_hz > T::zero(),
+            "LowPassFilter1stOrder: Cutoff frequency must be greater than zero."
+        );
+
+        Self::from_time_constant(time_constant_from_cutoff(cutoff_freq_hz), dt)
+    }
+
+    pub fn update(&mut self, data: T) {
+        self.update_with_alpha(data, self.alpha);
+    }
+
+    /// Update with the time elapsed since the previous data point, for sensors without a
+    /// fixed sample rate. The alpha for this data point is computed from the time constant,
+    /// so the filter must be constructed with from_time_constant or from_cutoff_frequency.
+    /// The constructed alpha is kept for update() and get_alpha().
+    /// dt: time since the previous data point [s]
+    pub fn update_with_dt(&mut self, data: T, dt: T) {
+        let time_constant_s = self.time_constant_s.expect(
+            "LowPassFilter1stOrder: update_with_dt needs a time constant, \
+             construct with from_time_constant or from_cutoff_frequency",
+        );
+        assert!(
+            dt > T::zero(),
+            "LowPassFilter1stOrder: Elapsed time, dt, must be greater than zero."
+        );
+
+        self.update_with_alpha(data, smoothing_factor(time_constant_s, dt));
+    }
+
+    fn update_with_alpha(&mut self, data: T, alpha: T) {
         if !(self.initialized) {
             self.avg = data;
             self.initialized = true;
         }
 
-        self.avg = self.alpha * self.avg + (1.0 - self.alpha) * data;
+        self.avg = exponential_smoothing(data, self.avg, alpha);
     }
 
-    pub fn get_average(&self) -> f64 {
+    pub fn get_average(&self) -> T {
         self.avg
     }
+
+    pub fn get_alpha(&self) -> T {
+        self.alpha
+    }
+}
+
+impl<T: Float> Filter<T> for LowPassFilter1stOrder<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_average()
+    }
+
+    fn reset(&mut self) {
+        // alpha and the time constant only change at construction, keep them
+        self.avg = T::zero();
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// 1st order high pass filter, the counterpart of LowPassFilter1stOrder.
+/// Passes changes in the data and removes its slowly varying part, e.g. a drifting offset.
+/// y_k = alpha * y_k-1 + alpha * (x_k - x_k-1)
+/// T: floating point sample type, f32 or f64
+pub struct HighPassFilter1stOrder<T: Float = f64> {
+    output: T,
+    prev_data: T,
+    alpha: T,
+    initialized: bool,
+}
+
+impl<T: Float> HighPassFilter1stOrder<T> {
+    /// alpha: 0.0 < alpha < 1.0, a larger alpha passes lower frequencies
+    pub fn new(alpha: T) -> Self {
+        assert!(
+            (alpha > T::zero()) && (alpha < T::one()),
+            "HighPassFilter1stOrder: Smoothing factor, alpha, \
+             must be between zero and one, 0.0 < alpha < 1.0"
+        );
+
+        Self {
+            output: T::zero(),
+            prev_data: T::zero(),
+            alpha,
+            initialized: false,
+        }
+    }
+
+    /// cutoff_freq_hz: -3 dB frequency, below this the data is attenuated
+    /// dt: sample period [s]
+    /// alpha = tau / (tau + dt), tau = 1 / (2 pi cutoff_freq_hz)
+    pub fn from_cutoff_frequency(cutoff_freq_hz: T, dt: T) -> Self {
+        assert!(
+            (cutoff_freq_hz > T::zero()) && (dt > T::zero()),
+            "HighPassFilter1stOrder: Cutoff frequency and sample period must be greater than zero."
+        );
+
+        Self::new(smoothing_factor(
+            time_constant_from_cutoff(cutoff_freq_hz),
+            dt,
+        ))
+    }
+
+    pub fn update(&mut self, data: T) {
+        // The first data point has no change to pass, output stays at zero
+        if !(self.initialized) {
+            self.prev_data = data;
+            self.initialized = true;
+        }
+
+        self.output = self.alpha * (self.output + data - self.prev_data);
+        self.prev_data = data;
+    }
+
+    pub fn get_output(&self) -> T {
+        self.output
+    }
+}
+
+impl<T: Float> Filter<T> for HighPassFilter1stOrder<T> {
+    fn update(&mut self, data: T) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> T {
+        self.get_output()
+    }
+
+    fn reset(&mut self) {
+        self.output = T::zero();
+        self.prev_data = T::zero();
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// Moving average of several channels at once, e.g. the three axes of an accelerometer.
+/// All channels share one ring buffer of samples, sized for the largest window; a channel
+/// with a smaller window drops its oldest sample from further back in the same buffer.
+/// Like MovingAverageFilter, the buffer is initialized with the first data vector.
+pub struct MultiChannelMovingAverageFilter {
+    avg: DVector<f64>,
+    window_sizes: Vec<usize>, // 'n' in formula, per channel
+    data_buff: Option<Vec<DVector<f64>>>,
+    newest_data_idx: usize,
+}
+
+impl MultiChannelMovingAverageFilter {
+    /// Same window size on every channel
+    pub fn new(num_channels: usize, window_size: usize) -> Self {
+        Self::from_window_sizes(&vec![window_size; num_channels])
+    }
+
+    /// One window size per channel
+    pub fn from_window_sizes(window_sizes: &[usize]) -> Self {
+        assert!(
+            !window_sizes.is_empty(),
+            "MultiChannelMovingAverageFilter: Number of channels must be greater than zero."
+        );
+        assert!(
+            window_sizes.iter().all(|&window_size| window_size > 0),
+            "MultiChannelMovingAverageFilter: Window size must be greater than zero."
+        );
+
+        Self {
+            avg: DVector::zeros(window_sizes.len()),
+            window_sizes: window_sizes.to_vec(),
+            data_buff: None,
+            newest_data_idx: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: DVector<f64>) {
+        assert_eq!(
+            data.len(),
+            self.avg.len(),
+            "MultiChannelMovingAverageFilter: Data length must match the number of channels."
+        );
+
+        if let Some(ref mut data_buff) = self.data_buff {
+            let buff_len = data_buff.len();
+            self.newest_data_idx = (self.newest_data_idx + 1) % buff_len;
+
+            // Calculate rolling average; the sample leaving channel i's window was added
+            // window_sizes[i] updates ago
+            for (i, &window_size) in self.window_sizes.iter().enumerate() {
+                let oldest_data_idx = (self.newest_data_idx + buff_len - window_size) % buff_len;
+                let oldest_data = data_buff[oldest_data_idx][i];
+                self.avg[i] += (data[i] - oldest_data) / window_size as f64;
+            }
+
+            // Book keeping to replace the oldest data point in the buffer with the newest
+            data_buff[self.newest_data_idx] = data;
+        } else {
+            // Inititially there are not enough data points for rolling average
+            // This implementation initializes the buffer with the first data value
+            let buff_len = *self.window_sizes.iter().max().unwrap();
+            self.avg = data.clone();
+            self.data_buff = Some(vec![data; buff_len]);
+        }
+    }
+
+    pub fn get_average(&self) -> &DVector<f64> {
+        &self.avg
+    }
+
+    pub fn get_window_sizes(&self) -> &[usize] {
+        &self.window_sizes
+    }
+}
+
+impl Filter<DVector<f64>> for MultiChannelMovingAverageFilter {
+    fn update(&mut self, data: DVector<f64>) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> DVector<f64> {
+        self.get_average().clone()
+    }
+
+    fn reset(&mut self) {
+        self.avg.fill(0.0);
+        self.data_buff = None;
+        self.newest_data_idx = 0;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// 1st order low pass filter of several channels at once, e.g. the three axes of an
+/// accelerometer, with a smoothing factor per channel.
+pub struct MultiChannelLowPassFilter1stOrder {
+    avg: DVector<f64>,
+    alphas: DVector<f64>,
+    initialized: bool,
+}
+
+impl MultiChannelLowPassFilter1stOrder {
+    /// Same smoothing factor on every channel, 0.0 < alpha < 1.0
+    pub fn new(num_channels: usize, alpha: f64) -> Self {
+        Self::from_alphas(DVector::from_element(num_channels, alpha))
+    }
+
+    /// One smoothing factor per channel, 0.0 < alpha < 1.0
+    pub fn from_alphas(alphas: DVector<f64>) -> Self {
+        assert!(
+            !alphas.is_empty(),
+            "MultiChannelLowPassFilter1stOrder: Number of channels must be greater than zero."
+        );
+        assert!(
+            alphas.iter().all(|&alpha| (alpha > 0.0) && (alpha < 1.0)),
+            "MultiChannelLowPassFilter1stOrder: Smoothing factor, alpha, \
+             must be between zero and one, 0.0 < alpha < 1.0"
+        );
+
+        Self {
+            avg: DVector::zeros(alphas.len()),
+            alphas,
+            initialized: false,
+        }
+    }
+
+    pub fn update(&mut self, data: DVector<f64>) {
+        assert_eq!(
+            data.len(),
+            self.avg.len(),
+            "MultiChannelLowPassFilter1stOrder: Data length must match the number of channels."
+        );
+
+        if !(self.initialized) {
+            self.avg = data;
+            self.initialized = true;
+            return;
+        }
+
+        for i in 0..self.avg.len() {
+            self.avg[i] = exponential_smoothing(data[i], self.avg[i], self.alphas[i]);
+        }
+    }
+
+    pub fn get_average(&self) -> &DVector<f64> {
+        &self.avg
+    }
+
+    pub fn get_alphas(&self) -> &DVector<f64> {
+        &self.alphas
+    }
+}
+
+impl Filter<DVector<f64>> for MultiChannelLowPassFilter1stOrder {
+    fn update(&mut self, data: DVector<f64>) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> DVector<f64> {
+        self.get_average().clone()
+    }
+
+    fn reset(&mut self) {
+        self.avg.fill(0.0);
+        self.initialized = false;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+/// Fuses an integrated rate signal, e.g. a gyro, with an absolute reference of the same
+/// quantity, e.g. an accelerometer tilt angle. The rate path is high-pass filtered and the
+/// reference path low-pass filtered, with the crossover at 1 / (2 pi tau) Hz.
+/// estimate = alpha * (estimate + rate * dt) + (1 - alpha) * reference
+/// alpha = tau / (tau + dt)
+pub struct ComplementaryFilter1stOrder {
+    estimate: f64,
+    alpha: f64,
+    dt: f64,
+    initialized: bool,
+}
+
+impl ComplementaryFilter1stOrder {
+    /// time_constant_s: tau, time constant of the crossover [s]
+    /// dt: sample period [s]
+    pub fn new(time_constant_s: f64, dt: f64) -> Self {
+        assert!(
+            (time_constant_s > 0.0) && (dt > 0.0),
+            "ComplementaryFilter1stOrder: Time constant and sample period must be greater than zero."
+        );
+
+        Self {
+            estimate: 0.0,
+            alpha: smoothing_factor(time_constant_s, dt),
+            dt,
+            initialized: false,
+        }
+    }
+
+    /// crossover_freq_hz: below this the reference dominates, above it the rate signal
+    /// dt: sample period [s]
+    pub fn from_crossover_frequency(crossover_freq_hz: f64, dt: f64) -> Self {
+        assert!(
+            crossover_freq_hz > 0.0,
+            "ComplementaryFilter1stOrder: Crossover frequency must be greater than zero."
+        );
+
+        Self::new(time_constant_from_cutoff(crossover_freq_hz), dt)
+    }
+
+    pub fn update(&mut self, rate: f64, reference: f64) {
+        if !(self.initialized) {
+            self.estimate = reference;
+            self.initialized = true;
+        }
+
+        let integrated_rate = self.estimate + rate * self.dt;
+        self.estimate = exponential_smoothing(reference, integrated_rate, self.alpha);
+    }
+
+    pub fn get_estimate(&self) -> f64 {
+        self.estimate
+    }
+}
+
+/// Complementary filter with integral correction of a constant bias on the rate signal.
+/// The error between the reference and the estimate feeds back through a PI controller,
+/// so a constant rate bias is learned instead of leaving a steady state offset.
+/// error = reference - estimate
+/// estimate += (rate + kp * error + ki * integral(error)) * dt
+/// kp = 2 * zeta * wc, ki = wc^2 with wc = 1 / tau and damping zeta = 1 / sqrt(2)
+pub struct ComplementaryFilter2ndOrder {
+    estimate: f64,
+    bias_correction: f64, // ki * integral(error), the negated rate bias
+    kp: f64,
+    ki: f64,
+    dt: f64,
+    initialized: bool,
+}
+
+impl ComplementaryFilter2ndOrder {
+    /// time_constant_s: tau, time constant of the crossover [s]
+    /// dt: sample period [s]
+    pub fn new(time_constant_s: f64, dt: f64) -> Self {
+        assert!(
+            (time_constant_s > 0.0) && (dt > 0.0),
+            "ComplementaryFilter2ndOrder: Time constant and sample period must be greater than zero."
+        );
+
+        let crossover_rad_s = 1.0 / time_constant_s;
+
+        Self {
+            estimate: 0.0,
+            bias_correction: 0.0,
+            kp: 2.0 * std::f64::consts::FRAC_1_SQRT_2 * crossover_rad_s,
+            ki: crossover_rad_s.powi(2),
+            dt,
+            initialized: false,
+        }
+    }
+
+    /// crossover_freq_hz: below this the reference dominates, above it the rate signal
+    /// dt: sample period [s]
+    pub fn from_crossover_frequency(crossover_freq_hz: f64, dt: f64) -> Self {
+        assert!(
+            crossover_freq_hz > 0.0,
+            "ComplementaryFilter2ndOrder: Crossover frequency must be greater than zero."
+        );
+
+        Self::new(time_constant_from_cutoff(crossover_freq_hz), dt)
+    }
+
+    pub fn update(&mut self, rate: f64, reference: f64) {
+        if !(self.initialized) {
+            self.estimate = reference;
+            self.initialized = true;
+        }
+
+        let error = reference - self.estimate;
+        self.bias_correction += self.ki * error * self.dt;
+        self.estimate += (rate + self.kp * error + self.bias_correction) * self.dt;
+    }
+
+    pub fn get_estimate(&self) -> f64 {
+        self.estimate
+    }
+
+    /// Estimated bias of the rate signal, in the rate's units
+    pub fn get_rate_bias(&self) -> f64 {
+        -self.bias_correction
+    }
+}
+
+/// Fixed size buffer of the most recent data points, shared by the sliding window filters.
+/// Once full, pushing a new data point overwrites the oldest one.
+pub(crate) struct RingBuffer<T> {
+    data_buff: Vec<T>,
+    window_size: usize,
+    oldest_data_idx: usize,
+}
+
+impl<T: Copy> RingBuffer<T> {
+    /// Empty buffer, filled by the first window_size pushes
+    pub(crate) fn empty(window_size: usize) -> Self {
+        Self {
+            data_buff: Vec::with_capacity(window_size),
+            window_size,
+            oldest_data_idx: 0,
+        }
+    }
+
+    /// Full buffer of window_size copies of value
+    pub(crate) fn filled(value: T, window_size: usize) -> Self {
+        Self {
+            data_buff: vec![value; window_size],
+            window_size,
+            oldest_data_idx: 0,
+        }
+    }
+
+    /// Add data, returning the oldest data point it replaced, or None if the buffer
+    /// wasn't full yet
+    pub(crate) fn push(&mut self, data: T) -> Option<T> {
+        if self.data_buff.len() < self.window_size {
+            self.data_buff.push(data);
+            return None;
+        }
+
+        let oldest_data = std::mem::replace(&mut self.data_buff[self.oldest_data_idx], data);
+        self.oldest_data_idx = (self.oldest_data_idx + 1) % self.window_size;
+        Some(oldest_data)
+    }
+
+    /// Number of data points in the buffer
+    pub(crate) fn len(&self) -> usize {
+        self.data_buff.len()
+    }
+
+    /// Buffer contents, in storage order rather than time order
+    pub(crate) fn as_slice(&self) -> &[T] {
+        &self.data_buff
+    }
+
+    /// Buffer contents in time order, oldest first
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        let (newer, older) = self.data_buff.split_at(self.oldest_data_idx);
+        older.iter().chain(newer.iter())
+    }
 }
 
 /**
  * The generic form of the equation used in the averaging filter and 1st order low pass filter.
  *
- * Alpha is called the smoothing factor.
- * A larger alpha gives more weight to recent data, resulting in a faster response to changes;
- * leads to less smoothing and more sensitivity to recent values.
- * A smaller alpha gives more weight to past data, resulting in a slower response to changes,
- * but leading to smoother outputs and more memory of past values.
- * .
+ * average = alpha * previous_average + (1 - alpha) * data
+ *
+ * Alpha is called the smoothing factor; it is the weight on the previous average, not on the
+ * new data.
+ * A larger alpha (closer to 1) gives more weight to past data, resulting in a slower response
+ * to changes, but smoother outputs and more memory of past values.
+ * A smaller alpha (closer to 0) gives more weight to recent data, resulting in a faster
+ * response to changes; leads to less smoothing and more sensitivity to recent values.
  */
-fn exponential_smoothing(data: f64, previous_average: f64, alpha: f64) -> f64 {
+fn exponential_smoothing<T: Float>(data: T, previous_average: T, alpha: T) -> T {
     assert!(
-        (alpha > 0.0) && (alpha < 1.0),
+        (alpha > T::zero()) && (alpha < T::one()),
         "exponential_smoothing: Smoothing factor, alpha,
          must be between zero and one, 0.0 < alpha < 1.0"
     );
 
-    alpha * previous_average + (1.0 - alpha) * data
+    alpha * previous_average + (T::one() - alpha) * data
+}
+
+/// Time constant of a 1st order filter with the given -3 dB frequency, tau = 1 / (2 pi fc)
+fn time_constant_from_cutoff<T: Float>(cutoff_freq_hz: T) -> T {
+    T::one() / (T::from(2.0 * std::f64::consts::PI).unwrap() * cutoff_freq_hz)
+}
+
+/// Smoothing factor of a 1st order filter sampled every dt, alpha = tau / (tau + dt).
+/// When dt is tiny next to tau, e.g. in f32, alpha would round to 1.0, so it's held just
+/// below one.
+fn smoothing_factor<T: Float>(time_constant_s: T, dt: T) -> T {
+    (time_constant_s / (time_constant_s + dt)).min(T::one() - T::epsilon())
 }
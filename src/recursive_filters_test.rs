@@ -1,8 +1,22 @@
-use crate::sensor_spoofs;
-use crate::utils::{ascending_float_range, PlotLabels};
+use crate::attitude_reference_system_test::load_ars_data;
+use crate::sensor_noise::{
+    BiasDriftNoise, GaussianNoise, NoiseChain, NoiseModel, OutlierMixture, Quantization,
+    StudentTNoise, UniformNoise,
+};
+use crate::sensor_spoofs::VoltageSensor;
+use crate::utils::{ascending_float_range, plot_two_lines, read_mat_file_arrays, PlotLabels};
+use kalman_filter_for_beginners_rust::biquad_filters::BiquadCascade;
+use kalman_filter_for_beginners_rust::filter_pipeline::{FilterPipeline, StageConfig};
+use kalman_filter_for_beginners_rust::fixed_point_filters::{
+    FixedLowPassFilter1stOrder, FixedMovingAverageFilter, FixedPoint, Q15, Q31,
+};
+use kalman_filter_for_beginners_rust::kalman_filter::{ScalarKalmanFilter, SystemModel};
+use kalman_filter_for_beginners_rust::outlier_filters::{HampelFilter, MedianFilter};
 use kalman_filter_for_beginners_rust::recursive_filters::{
-    AverageFilter, LowPassFilter1stOrder, MovingAverageFilter,
+    filter_series, AverageFilter, Filter, FilterIterator, HighPassFilter1stOrder,
+    LowPassFilter1stOrder, MovingAverageFilter, MultiChannelLowPassFilter1stOrder, WarmUp,
 };
+use nalgebra::{DMatrix, DVector};
 use plotters::prelude::*;
 
 pub fn average_filter_example() {
@@ -11,21 +25,16 @@ pub fn average_filter_example() {
 
     let num_data_pts: usize = times_s.len();
 
-    let mut raw_data = Vec::<f64>::with_capacity(num_data_pts);
-    let mut filtered_data = Vec::<f64>::with_capacity(num_data_pts);
+    let mut volt_sensor = VoltageSensor::from_seed(1);
+    let raw_data: Vec<f64> = (0..num_data_pts)
+        .map(|_| volt_sensor.measure().measurement)
+        .collect();
 
     // Initialize averaging filter
-    let mut avg_filt = AverageFilter::new();
+    let avg_filt = AverageFilter::new();
 
     // Run simulation
-    for _ in 0..num_data_pts {
-        let data_pt = sensor_spoofs::get_volt();
-        avg_filt.update(data_pt);
-
-        // Log data for plotting
-        raw_data.push(data_pt);
-        filtered_data.push(avg_filt.get_average());
-    }
+    let filtered_data: Vec<f64> = raw_data.iter().filtered(avg_filt).collect();
 
     // --- MAKE PLOTS --------------------------------------------------------//
     // Build and save graph using plotters crate; graph format based on textbook example
@@ -77,13 +86,12 @@ pub fn average_filter_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &RED,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -94,16 +102,15 @@ pub fn average_filter_example() {
             4, // Size of the points
             &RED,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data1_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data1_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -116,13 +123,12 @@ pub fn average_filter_example() {
                 .map(|(&x_val, &y_val)| (x_val, y_val)),
             &BLUE,
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() LineSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        );
+        });
 
     chart
         .draw_series(PointSeries::of_element(
@@ -133,16 +139,15 @@ pub fn average_filter_example() {
             3, // Size of the points
             &BLUE,
             &|coord, size, style| {
-                return EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled());
+                EmptyElement::at(coord) + Circle::new((0, 0), size, style.filled())
             },
         ))
-        .expect(
-            format!(
+        .unwrap_or_else(|_| {
+            panic!(
                 "draw_series() PointSeries {} failed",
                 plot_labels.y_axis_data2_label
             )
-            .as_str(),
-        )
+        })
         .label(plot_labels.y_axis_data2_label)
         .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Circle::new((0, 0), 3, BLUE.filled()));
 
@@ -150,8 +155,8 @@ pub fn average_filter_example() {
         .configure_series_labels()
         .position(SeriesLabelPosition::LowerRight)
         .margin(5)
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()
         .expect("configure_series_labels() failed");
 
@@ -173,23 +178,18 @@ pub fn moving_average_filter_example() {
         let dt: f64 = 0.02; // from example code
         let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
 
-        let mut raw_data = Vec::<f64>::with_capacity(num_data_pts);
-        let mut filtered_data = Vec::<f64>::with_capacity(num_data_pts);
+        let raw_data: Vec<f64> = match sonar_alt_arr.data() {
+            matfile::NumericData::Double { real, .. } => {
+                real.iter().take(num_data_pts).copied().collect()
+            }
+            _ => Vec::new(),
+        };
 
         // Initialize moving averaging filter, window size = 10
-        let mut mv_avg_filt = MovingAverageFilter::new(10);
+        let mv_avg_filt = MovingAverageFilter::new(10);
 
         // Run simulation
-        if let matfile::NumericData::Double { real, .. } = sonar_alt_arr.data() {
-            for i in 0..num_data_pts {
-                let data_pt = real[i];
-                mv_avg_filt.update(data_pt);
-
-                // Log data for plotting
-                raw_data.push(data_pt);
-                filtered_data.push(mv_avg_filt.get_average());
-            }
-        }
+        let filtered_data: Vec<f64> = raw_data.iter().filtered(mv_avg_filt).collect();
 
         // --- MAKE PLOTS ----------------------------------------------------//
         // Build and save graph using plotters crate; graph format based on textbook example
@@ -242,16 +242,15 @@ pub fn moving_average_filter_example() {
                 2, // Size of the points
                 &RED,
                 &|coord, size, style| {
-                    return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                    EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
                 },
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() PointSeries {} failed",
                     plot_labels.y_axis_data1_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data1_label)
             .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -264,22 +263,21 @@ pub fn moving_average_filter_example() {
                     .map(|(&x_val, &y_val)| (x_val, y_val)),
                 &BLUE,
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() LineSeries {} failed",
                     plot_labels.y_axis_data2_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data2_label)
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
         chart
             .configure_series_labels()
             .position(SeriesLabelPosition::LowerRight)
             .margin(5)
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()
             .expect("configure_series_labels() failed");
 
@@ -305,27 +303,20 @@ pub fn first_order_low_pass_filter_example() {
         let dt: f64 = 0.02; // from example code
         let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
 
-        let mut raw_data = Vec::<f64>::with_capacity(num_data_pts);
-        let mut filtered_data_40 = Vec::<f64>::with_capacity(num_data_pts);
-        let mut filtered_data_90 = Vec::<f64>::with_capacity(num_data_pts);
+        let raw_data: Vec<f64> = match sonar_alt_arr.data() {
+            matfile::NumericData::Double { real, .. } => {
+                real.iter().take(num_data_pts).copied().collect()
+            }
+            _ => Vec::new(),
+        };
 
         // Initialize moving averaging filter, alpha = 0.4 and alpha = 0.9
-        let mut mv_avg_filt_40 = LowPassFilter1stOrder::new(0.4);
-        let mut mv_avg_filt_90 = LowPassFilter1stOrder::new(0.9);
+        let mv_avg_filt_40 = LowPassFilter1stOrder::new(0.4);
+        let mv_avg_filt_90 = LowPassFilter1stOrder::new(0.9);
 
         // Run simulation
-        if let matfile::NumericData::Double { real, .. } = sonar_alt_arr.data() {
-            for i in 0..num_data_pts {
-                let data_pt = real[i];
-                mv_avg_filt_40.update(data_pt);
-                mv_avg_filt_90.update(data_pt);
-
-                // Log data for plotting
-                raw_data.push(data_pt);
-                filtered_data_40.push(mv_avg_filt_40.get_average());
-                filtered_data_90.push(mv_avg_filt_90.get_average());
-            }
-        }
+        let filtered_data_40: Vec<f64> = raw_data.iter().filtered(mv_avg_filt_40).collect();
+        let filtered_data_90: Vec<f64> = raw_data.iter().filtered(mv_avg_filt_90).collect();
 
         // --- MAKE PLOTS ----------------------------------------------------//
         // Build and save graph using plotters crate; graph format based on textbook example
@@ -378,16 +369,15 @@ pub fn first_order_low_pass_filter_example() {
                 2, // Size of the points
                 &RED,
                 &|coord, size, style| {
-                    return EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled());
+                    EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
                 },
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() PointSeries {} failed",
                     plot_labels.y_axis_data1_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data1_label)
             .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
 
@@ -400,22 +390,21 @@ pub fn first_order_low_pass_filter_example() {
                     .map(|(&x_val, &y_val)| (x_val, y_val)),
                 &BLUE,
             ))
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "draw_series() LineSeries {} failed",
                     plot_labels.y_axis_data2_label
                 )
-                .as_str(),
-            )
+            })
             .label(plot_labels.y_axis_data2_label)
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
         chart
             .configure_series_labels()
             .position(SeriesLabelPosition::LowerRight)
             .margin(5)
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()
             .expect("configure_series_labels() failed");
 
@@ -466,7 +455,7 @@ pub fn first_order_low_pass_filter_example() {
             ))
             .expect("draw_series() LineSeries alpha = 0.4 failed")
             .label("alpha = 0.4")
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
 
         // Plot the alpha = 0.9 data as a blue line
         chart
@@ -479,14 +468,14 @@ pub fn first_order_low_pass_filter_example() {
             ))
             .expect("draw_series() LineSeries alpha = 0.9 failed")
             .label("alpha = 0.9")
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
 
         chart
             .configure_series_labels()
             .position(SeriesLabelPosition::LowerRight)
             .margin(5)
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()
             .expect("configure_series_labels() failed");
 
@@ -497,3 +486,811 @@ pub fn first_order_low_pass_filter_example() {
         );
     }
 }
+
+pub fn low_pass_filter_variable_rate_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Variable rate low pass filter example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation & data logging; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let cutoff_freq_hz: f64 = 1.0;
+
+    // Reference filter sees every sample; the others miss every third one, so the time
+    // between their samples alternates between dt and 2 * dt
+    let mut lpf_full_rate = LowPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt);
+    let mut lpf_variable_dt = LowPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt);
+    let mut lpf_fixed_dt = LowPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt);
+
+    let mut times_s = Vec::<f64>::with_capacity(num_data_pts);
+    let mut full_rate_data = Vec::<f64>::with_capacity(num_data_pts);
+    let mut variable_dt_data = Vec::<f64>::with_capacity(num_data_pts);
+    let mut sum_sq_err_variable_dt = 0.0;
+    let mut sum_sq_err_fixed_dt = 0.0;
+    let mut prev_time_s = 0.0;
+
+    // Run simulation
+    for (i, &data_pt) in sonar_alt_arrs[0].iter().take(num_data_pts).enumerate() {
+        let time_s = i as f64 * dt;
+        lpf_full_rate.update(data_pt);
+
+        if i % 3 == 2 {
+            continue;
+        }
+
+        // The first data point only initializes the filter, any elapsed time will do
+        let elapsed_s = if i == 0 { dt } else { time_s - prev_time_s };
+        lpf_variable_dt.update_with_dt(data_pt, elapsed_s);
+        lpf_fixed_dt.update(data_pt);
+        prev_time_s = time_s;
+
+        sum_sq_err_variable_dt +=
+            (lpf_variable_dt.get_average() - lpf_full_rate.get_average()).powi(2);
+        sum_sq_err_fixed_dt += (lpf_fixed_dt.get_average() - lpf_full_rate.get_average()).powi(2);
+
+        // Log data for plotting
+        times_s.push(time_s);
+        full_rate_data.push(lpf_full_rate.get_average());
+        variable_dt_data.push(lpf_variable_dt.get_average());
+    }
+
+    let num_logged_pts = times_s.len() as f64;
+    println!(
+        "Irregular rate LPF RMS difference from full rate [m]: variable dt {:.3}, fixed dt {:.3}",
+        (sum_sq_err_variable_dt / num_logged_pts).sqrt(),
+        (sum_sq_err_fixed_dt / num_logged_pts).sqrt()
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/03d_LowPassFilter1stOrder_VariableRate.png".to_string(),
+        title: "1st Order LPF, Irregular Sample Rate".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Altitude [m]".to_string(),
+        y_axis_data1_label: "Full rate".to_string(),
+        y_axis_data2_label: "Variable dt".to_string(),
+    };
+
+    plot_two_lines(plot_labels, &times_s, &full_rate_data, &variable_dt_data);
+}
+
+pub fn first_order_high_pass_filter_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("High pass filter example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation & data logging; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+
+    let raw_data = &sonar_alt_arrs[0][..num_data_pts];
+
+    // Initialize high pass filter, cutoff frequency = 1 Hz
+    let hpf = HighPassFilter1stOrder::from_cutoff_frequency(1.0, dt);
+
+    // Run simulation
+    let filtered_data: Vec<f64> = raw_data.iter().filtered(hpf).collect();
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    // Build and save graph using plotters crate; graph format based on textbook example
+    let x_axis_data = &times_s;
+    let y_axis_data1 = raw_data;
+    let y_axis_data2 = &filtered_data;
+
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/03c_HighPassFilter1stOrder.png".to_string(),
+        title: "1st Order High Pass Filter".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Altitude [m]".to_string(),
+        y_axis_data1_label: "Measured".to_string(),
+        y_axis_data2_label: "HPF".to_string(),
+    };
+
+    let root = BitMapBackend::new(&plot_labels.plot_pathname, (640, 480)).into_drawing_area();
+    let _ = root.fill(&WHITE);
+
+    // Configure the chart; the high pass output is centered on zero, below the altitude
+    let mut chart = ChartBuilder::on(&root)
+        .caption(plot_labels.title, ("sans-serif", 30).into_font())
+        .margin(25)
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0f64..10f64, -30f64..120f64)
+        .expect("ChartBuilder failed");
+
+    // Configure mesh with axis labels and grid lines
+    chart
+        .configure_mesh()
+        .x_labels(20) // increments of 1
+        .y_labels(15) // increments of 10
+        .x_desc(plot_labels.x_axis_label) // Label for the x-axis
+        .y_desc(plot_labels.y_axis_label) // Label for the y-axis
+        .x_label_style(("sans-serif", 18).into_font())
+        .y_label_style(("sans-serif", 18).into_font())
+        .x_label_formatter(&|x| format!("{}", *x as i64))
+        .y_label_formatter(&|y| format!("{}", *y as i64))
+        .draw()
+        .expect("configure_mesh() failed");
+
+    // Plot the raw data as red points
+    chart
+        .draw_series(PointSeries::of_element(
+            x_axis_data
+                .iter()
+                .zip(y_axis_data1.iter())
+                .map(|(&x, &y)| (x, y)),
+            2, // Size of the points
+            &RED,
+            &|coord, size, style| {
+                EmptyElement::at(coord) + Cross::new((0, 0), size, style.filled())
+            },
+        ))
+        .unwrap_or_else(|_| {
+            panic!(
+                "draw_series() PointSeries {} failed",
+                plot_labels.y_axis_data1_label
+            )
+        })
+        .label(plot_labels.y_axis_data1_label)
+        .legend(|(x, y)| EmptyElement::at((x + 10, y)) + Cross::new((0, 0), 3, RED.filled()));
+
+    // Plot the filtered data as a blue line
+    chart
+        .draw_series(LineSeries::new(
+            x_axis_data
+                .iter()
+                .zip(y_axis_data2.iter())
+                .map(|(&x_val, &y_val)| (x_val, y_val)),
+            &BLUE,
+        ))
+        .unwrap_or_else(|_| {
+            panic!(
+                "draw_series() LineSeries {} failed",
+                plot_labels.y_axis_data2_label
+            )
+        })
+        .label(plot_labels.y_axis_data2_label)
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .margin(5)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .expect("configure_series_labels() failed");
+
+    let _ = root.present();
+
+    println!(
+        "High pass filter plot written: {}",
+        plot_labels.plot_pathname
+    );
+}
+
+pub fn sonar_filter_comparison_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Filter comparison example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+    let raw_data = &sonar_alt_arrs[0][..num_data_pts];
+
+    // Position and velocity model from the sonar Kalman filter example
+    let system_model = SystemModel::new(
+        DMatrix::from_row_slice(2, 2, &[1.0, 0.1, 0.0, 1.0]),
+        DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]),
+        DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+        DMatrix::from_row_slice(1, 1, &[10.0]),
+    );
+
+    // Every filter runs through the same code path
+    let mut filters: Vec<(&str, Box<dyn Filter>)> = vec![
+        ("Moving average", Box::new(MovingAverageFilter::new(10))),
+        ("LPF", Box::new(LowPassFilter1stOrder::new(0.7))),
+        (
+            "Kalman Filter",
+            Box::new(ScalarKalmanFilter::new(
+                system_model,
+                DVector::from_column_slice(&[0.0, 20.0]),
+                DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+                0,
+            )),
+        ),
+    ];
+
+    for (plot_suffix, (filter_name, filt)) in ["a", "b", "c"].iter().zip(filters.iter_mut()) {
+        let filtered_data = filter_series(filt.as_mut(), raw_data);
+
+        // --- MAKE PLOTS ----------------------------------------------------//
+        let plot_labels = PlotLabels {
+            plot_pathname: format!("./plots/16{}_SonarFilterComparison.png", plot_suffix),
+            title: format!("{} on Sonar Altitude", filter_name),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: "Altitude [m]".to_string(),
+            y_axis_data1_label: "Measured".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(
+            plot_labels,
+            &times_s[..num_data_pts],
+            raw_data,
+            &filtered_data,
+        );
+    }
+}
+
+pub fn fixed_point_low_pass_filter_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Fixed point filter example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+    let raw_data = &sonar_alt_arrs[0][..num_data_pts];
+
+    // Q15 and Q31 cover [-1, 1); scale altitudes so the full range of the sonar fits
+    const ALT_FULL_SCALE_M: f64 = 128.0;
+
+    let mut lpf_f64 = LowPassFilter1stOrder::new(0.7);
+    let mut lpf_f32 = LowPassFilter1stOrder::<f32>::new(0.7);
+    let mut lpf_q15 = FixedLowPassFilter1stOrder::<Q15>::from_real_alpha(0.7);
+    let mut mv_avg_f64 = MovingAverageFilter::new(10);
+    let mut mv_avg_q15 = FixedMovingAverageFilter::<Q15>::new(10);
+    let mut lpf_q31 = FixedLowPassFilter1stOrder::<Q31>::from_real_alpha(0.7);
+    let mut mv_avg_q31 = FixedMovingAverageFilter::<Q31>::new(10);
+
+    let mut filtered_data_f64 = Vec::<f64>::with_capacity(num_data_pts);
+    let mut filtered_data_q15 = Vec::<f64>::with_capacity(num_data_pts);
+    let mut max_err_f32: f64 = 0.0;
+    let mut max_err_mv_avg_q15: f64 = 0.0;
+    let mut max_err_lpf_q31: f64 = 0.0;
+    let mut max_err_mv_avg_q31: f64 = 0.0;
+
+    // Run simulation
+    for &data_pt in raw_data {
+        let data_pt_q15 = Q15::from_real(data_pt / ALT_FULL_SCALE_M);
+        let data_pt_q31 = Q31::from_real(data_pt / ALT_FULL_SCALE_M);
+
+        lpf_f64.update(data_pt);
+        lpf_f32.update(data_pt as f32);
+        lpf_q15.update(data_pt_q15);
+        mv_avg_f64.update(data_pt);
+        mv_avg_q15.update(data_pt_q15);
+        lpf_q31.update(data_pt_q31);
+        mv_avg_q31.update(data_pt_q31);
+
+        // Log data for plotting, converting the fixed point output back to meters
+        filtered_data_f64.push(lpf_f64.get_average());
+        filtered_data_q15.push(lpf_q15.get_average().to_real() * ALT_FULL_SCALE_M);
+
+        max_err_f32 = max_err_f32.max((lpf_f32.get_average() as f64 - lpf_f64.get_average()).abs());
+        max_err_mv_avg_q15 = max_err_mv_avg_q15.max(
+            (mv_avg_q15.get_average().to_real() * ALT_FULL_SCALE_M - mv_avg_f64.get_average())
+                .abs(),
+        );
+        max_err_lpf_q31 = max_err_lpf_q31.max(
+            (lpf_q31.get_average().to_real() * ALT_FULL_SCALE_M - lpf_f64.get_average()).abs(),
+        );
+        max_err_mv_avg_q31 = max_err_mv_avg_q31.max(
+            (mv_avg_q31.get_average().to_real() * ALT_FULL_SCALE_M - mv_avg_f64.get_average())
+                .abs(),
+        );
+    }
+
+    let max_err_lpf_q15 = filtered_data_f64
+        .iter()
+        .zip(filtered_data_q15.iter())
+        .map(|(f, q)| (f - q).abs())
+        .fold(0.0, f64::max);
+    println!(
+        "Max difference from f64 [m]: LPF f32 {:.2e}, LPF Q15 {:.2e}, moving average Q15 {:.2e}",
+        max_err_f32, max_err_lpf_q15, max_err_mv_avg_q15
+    );
+    println!(
+        "Max difference from f64 [m]: LPF Q31 {:.2e}, moving average Q31 {:.2e}",
+        max_err_lpf_q31, max_err_mv_avg_q31
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/17_FixedPointLowPassFilter.png".to_string(),
+        title: "Fixed Point 1st Order Low Pass Filter".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Altitude [m]".to_string(),
+        y_axis_data1_label: "LPF f64".to_string(),
+        y_axis_data2_label: "LPF Q15".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s[..num_data_pts],
+        &filtered_data_f64,
+        &filtered_data_q15,
+    );
+}
+
+pub fn multichannel_low_pass_filter_example() {
+    // Load the textbook accelerometer recording, or the simulated IMU standing in for it
+    let ((_, accel_data), _) = load_ars_data();
+    let accel_arrs: Vec<Vec<f64>> = (0..3)
+        .map(|axis| accel_data.iter().map(|accel| accel[axis]).collect())
+        .collect();
+
+    // Setup simulation & data logging
+    let num_data_pts: usize = accel_arrs.iter().map(|arr| arr.len()).min().unwrap();
+
+    let dt: f64 = 0.01; // attitude reference system sample period
+    let times_s: Vec<f64> = (0..num_data_pts).map(|i| i as f64 * dt).collect();
+
+    let mut filtered_data: [Vec<f64>; 3] =
+        std::array::from_fn(|_| Vec::<f64>::with_capacity(num_data_pts));
+
+    // One filter for all three axes, with the z axis smoothed more heavily
+    let mut lpf = MultiChannelLowPassFilter1stOrder::from_alphas(DVector::from_column_slice(&[
+        0.9, 0.9, 0.95,
+    ]));
+
+    // Run simulation
+    let [fx, fy, fz] = [&accel_arrs[0], &accel_arrs[1], &accel_arrs[2]];
+    for ((&x, &y), &z) in fx.iter().zip(fy).zip(fz) {
+        lpf.update(DVector::from_column_slice(&[x, y, z]));
+
+        // Log data for plotting
+        for (axis, filtered_axis_data) in filtered_data.iter_mut().enumerate() {
+            filtered_axis_data.push(lpf.get_average()[axis]);
+        }
+    }
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    for (axis, (axis_name, plot_suffix)) in ["x", "y", "z"].iter().zip(["a", "b", "c"]).enumerate()
+    {
+        let plot_labels = PlotLabels {
+            plot_pathname: format!("./plots/18{}_MultiChannelLowPassFilter.png", plot_suffix),
+            title: format!("Multichannel LPF, {} axis", axis_name),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: "Acceleration [m/s^2]".to_string(),
+            y_axis_data1_label: "Measured".to_string(),
+            y_axis_data2_label: "LPF".to_string(),
+        };
+
+        plot_two_lines(
+            plot_labels,
+            &times_s,
+            &accel_arrs[axis][..num_data_pts],
+            &filtered_data[axis],
+        );
+    }
+}
+
+pub fn biquad_vibration_filter_example() {
+    // Setup simulation & data logging; accelerometer sampled at 100 Hz for 10 s
+    let dt: f64 = 0.01;
+    let times_s: Vec<f64> = ascending_float_range(0.0, 10.0, dt);
+
+    let num_data_pts: usize = times_s.len();
+
+    // Slow motion to keep, motor vibration at 25 Hz and 40 Hz to remove [m/s^2]
+    let true_accel = |t: f64| 0.5 * (2.0 * std::f64::consts::PI * 0.2 * t).sin();
+    let vibration = |t: f64| {
+        2.0 * (2.0 * std::f64::consts::PI * 25.0 * t).sin()
+            + 1.0 * (2.0 * std::f64::consts::PI * 40.0 * t).sin()
+    };
+
+    // Both filters cut off at 2 Hz; the 4th order Butterworth rolls off 80 dB/decade
+    // against 20 dB/decade for the 1st order low pass filter
+    let cutoff_freq_hz: f64 = 2.0;
+    let lpf = LowPassFilter1stOrder::from_cutoff_frequency(cutoff_freq_hz, dt);
+    let butterworth = BiquadCascade::butterworth_low_pass(4, cutoff_freq_hz, dt);
+
+    // Run simulation
+    let true_accels: Vec<f64> = times_s.iter().map(|&t| true_accel(t)).collect();
+    let raw_data: Vec<f64> = times_s
+        .iter()
+        .map(|&t| true_accel(t) + vibration(t))
+        .collect();
+    let lpf_data: Vec<f64> = raw_data.iter().filtered(lpf).collect();
+    let butterworth_data: Vec<f64> = raw_data.iter().filtered(butterworth).collect();
+
+    // Errors include the lag both filters add to the slow motion
+    let rms_err = |filtered_data: &[f64]| {
+        (filtered_data
+            .iter()
+            .zip(true_accels.iter())
+            .map(|(filtered, true_val)| (filtered - true_val).powi(2))
+            .sum::<f64>()
+            / num_data_pts as f64)
+            .sqrt()
+    };
+    println!(
+        "Vibration filter RMS error [m/s^2]: 1st order LPF {:.3}, 4th order Butterworth {:.3}",
+        rms_err(&lpf_data),
+        rms_err(&butterworth_data)
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/19_BiquadVibrationFilter.png".to_string(),
+        title: "Accelerometer Vibration Filtering".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Acceleration [m/s^2]".to_string(),
+        y_axis_data1_label: "1st order LPF".to_string(),
+        y_axis_data2_label: "4th order Butterworth".to_string(),
+    };
+
+    plot_two_lines(plot_labels, &times_s, &lpf_data, &butterworth_data);
+}
+
+pub fn sonar_dropout_filter_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Dropout filter example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+    let clean_data = &sonar_alt_arrs[0][..num_data_pts];
+
+    let raw_data = add_sonar_dropouts(clean_data);
+
+    // Every filter runs through the same code path
+    let mut filters: Vec<(&str, Box<dyn Filter>)> = vec![
+        ("Moving average", Box::new(MovingAverageFilter::new(10))),
+        ("Median", Box::new(MedianFilter::new(9))),
+        ("Hampel", Box::new(HampelFilter::new(9, 3.0))),
+    ];
+
+    for (plot_suffix, (filter_name, filt)) in ["a", "b", "c"].iter().zip(filters.iter_mut()) {
+        let filtered_data = filter_series(filt.as_mut(), &raw_data);
+
+        let rms_err = (filtered_data
+            .iter()
+            .zip(clean_data.iter())
+            .map(|(filtered, clean)| (filtered - clean).powi(2))
+            .sum::<f64>()
+            / num_data_pts as f64)
+            .sqrt();
+        println!(
+            "{} RMS error from data without dropouts: {:.2} m",
+            filter_name, rms_err
+        );
+
+        // --- MAKE PLOTS ----------------------------------------------------//
+        let plot_labels = PlotLabels {
+            plot_pathname: format!("./plots/20{}_SonarDropoutFilter.png", plot_suffix),
+            title: format!("{} on Sonar Dropouts", filter_name),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: "Altitude [m]".to_string(),
+            y_axis_data1_label: "Measured".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(
+            plot_labels,
+            &times_s[..num_data_pts],
+            &raw_data,
+            &filtered_data,
+        );
+    }
+}
+
+pub fn sonar_dropout_pipeline_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Filter pipeline example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+    let raw_data = add_sonar_dropouts(&sonar_alt_arrs[0][..num_data_pts]);
+
+    // Remove the dropouts, then smooth, then estimate with the sonar Kalman filter model;
+    // the whole chain is plain data, so it could equally be read from a settings file
+    let mut pipeline = FilterPipeline::from_config(&[
+        (
+            "hampel",
+            StageConfig::Hampel {
+                window_size: 9,
+                num_mads: 3.0,
+            },
+        ),
+        ("lpf", StageConfig::LowPass { alpha: 0.5 }),
+        (
+            "kalman",
+            StageConfig::ScalarKalman {
+                a: DMatrix::from_row_slice(2, 2, &[1.0, 0.1, 0.0, 1.0]),
+                q: DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]),
+                h: DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+                r: 10.0,
+                initial_est_x: DVector::from_column_slice(&[0.0, 20.0]),
+                initial_est_p: DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+                output_state_idx: 0,
+            },
+        ),
+    ])
+    .expect("FilterPipeline::from_config() failed");
+    println!("Filter pipeline stages: {:?}", pipeline.get_stage_names());
+
+    let mut hampel_data = Vec::<f64>::with_capacity(num_data_pts);
+    let mut pipeline_data = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Run simulation
+    for &data_pt in raw_data.iter() {
+        pipeline.update(data_pt);
+
+        // Log data for plotting, tapping off the first stage as well as the output
+        hampel_data.push(pipeline.get_stage_output("hampel").unwrap());
+        pipeline_data.push(pipeline.get_output());
+    }
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/26_SonarDropoutPipeline.png".to_string(),
+        title: "Hampel, LPF, Kalman Filter Pipeline".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Altitude [m]".to_string(),
+        y_axis_data1_label: "Hampel stage".to_string(),
+        y_axis_data2_label: "Pipeline output".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s[..num_data_pts],
+        &hampel_data,
+        &pipeline_data,
+    );
+}
+
+/// A sonar that misses its echo reads zero; drop every 23rd reading, and a few in a row
+fn add_sonar_dropouts(clean_data: &[f64]) -> Vec<f64> {
+    clean_data
+        .iter()
+        .enumerate()
+        .map(|(i, &data_pt)| {
+            if i % 23 == 7 || (300..303).contains(&i) {
+                0.0
+            } else {
+                data_pt
+            }
+        })
+        .collect()
+}
+
+pub fn measurement_noise_estimation_example() {
+    // Setup simulation & data logging; a stationary voltage sensor, as in the average filter
+    // example, sampled for 200 s
+    let dt: f64 = 0.2;
+    let times_s: Vec<f64> = ascending_float_range(0.0, 200.0, dt);
+
+    let num_data_pts: usize = times_s.len();
+
+    let mut cumulative_std_devs = Vec::<f64>::with_capacity(num_data_pts);
+    let mut windowed_std_devs = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Cumulative statistics over all data, and over the last 50 data points
+    let mut avg_filt = AverageFilter::new();
+    let mut mv_avg_filt = MovingAverageFilter::new(50);
+
+    let mut volt_sensor = VoltageSensor::from_seed(2);
+
+    // Run simulation
+    for _ in 0..num_data_pts {
+        let data_pt = volt_sensor.measure().measurement;
+        avg_filt.update(data_pt);
+        mv_avg_filt.update(data_pt);
+
+        // Log data for plotting
+        cumulative_std_devs.push(avg_filt.get_std_dev());
+        windowed_std_devs.push(mv_avg_filt.get_std_dev());
+    }
+
+    // The sensor noise is the measurement noise of a Kalman filter, R = variance
+    println!(
+        "Voltage sensor: average {:.2} V, std dev {:.2} V, R estimate {:.2} V^2 (true R = 16)",
+        avg_filt.get_average(),
+        avg_filt.get_std_dev(),
+        avg_filt.get_variance()
+    );
+    println!(
+        "Voltage sensor, last 50 data points: std dev {:.2} V, min {:.2} V, max {:.2} V",
+        mv_avg_filt.get_std_dev(),
+        mv_avg_filt.get_min(),
+        mv_avg_filt.get_max()
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/21_MeasurementNoiseEstimation.png".to_string(),
+        title: "Voltage Sensor Noise".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Std dev [V]".to_string(),
+        y_axis_data1_label: "Last 50 data points".to_string(),
+        y_axis_data2_label: "All data".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s,
+        &windowed_std_devs,
+        &cumulative_std_devs,
+    );
+}
+
+pub fn sensor_noise_robustness_example() {
+    // Setup simulation; the voltage sensor of the average filter example, sampled 500 times
+    let num_data_pts: usize = 500;
+    let sample_idxs: Vec<f64> = (0..num_data_pts).map(|i| i as f64).collect();
+
+    let noise_names = [
+        "Gaussian",
+        "Uniform",
+        "Student-t",
+        "Bias drift",
+        "Quantized",
+        "Outliers",
+    ];
+
+    println!(
+        "{:<14}{:>10}{:>10}{:>10}{:>10}{:>10}",
+        "RMS error [V]", "Average", "Mv avg 10", "LPF 0.9", "Median 9", "Hampel 9"
+    );
+    for noise_name in noise_names {
+        // Same seed for every model, so only the noise model differs between rows
+        let mut volt_sensor =
+            VoltageSensor::from_seed(3).with_noise(voltage_sensor_noise_model(noise_name));
+        let readings: Vec<_> = (0..num_data_pts).map(|_| volt_sensor.measure()).collect();
+        let raw_data: Vec<f64> = readings.iter().map(|reading| reading.measurement).collect();
+
+        let mut filters: Vec<Box<dyn Filter>> = vec![
+            Box::new(AverageFilter::new()),
+            Box::new(MovingAverageFilter::new(10)),
+            Box::new(LowPassFilter1stOrder::new(0.9)),
+            Box::new(MedianFilter::new(9)),
+            Box::new(HampelFilter::new(9, 3.0)),
+        ];
+        let rms_errs: Vec<f64> = filters
+            .iter_mut()
+            .map(|filt| {
+                let filtered_data = filter_series(filt.as_mut(), &raw_data);
+                (filtered_data
+                    .iter()
+                    .zip(readings.iter())
+                    .map(|(filtered, reading)| (filtered - reading.truth).powi(2))
+                    .sum::<f64>()
+                    / num_data_pts as f64)
+                    .sqrt()
+            })
+            .collect();
+        let rms_err_cols: String = rms_errs
+            .iter()
+            .map(|err| format!("{:>10.2}", err))
+            .collect();
+        println!("{:<14}{}", noise_name, rms_err_cols);
+
+        // --- MAKE PLOTS ----------------------------------------------------//
+        if noise_name == "Outliers" {
+            let plot_labels = PlotLabels {
+                plot_pathname: "./plots/27_SensorNoiseOutliers.png".to_string(),
+                title: "Voltage Sensor with 5% Outliers".to_string(),
+                x_axis_label: "Sample".to_string(),
+                y_axis_label: "Voltage [V]".to_string(),
+                y_axis_data1_label: "Measured".to_string(),
+                y_axis_data2_label: "Median".to_string(),
+            };
+
+            let median_data = filter_series(&mut MedianFilter::new(9), &raw_data);
+            plot_two_lines(plot_labels, &sample_idxs, &raw_data, &median_data);
+        }
+    }
+}
+
+/// Noise models for sensor_noise_robustness_example; each stands in for a different real
+/// sensor fault, all roughly as noisy as the textbook's 4 V Gaussian noise
+fn voltage_sensor_noise_model(noise_name: &str) -> Box<dyn NoiseModel> {
+    match noise_name {
+        "Gaussian" => Box::new(GaussianNoise::new(4.0)),
+        "Uniform" => Box::new(UniformNoise::new(4.0 * 3.0_f64.sqrt())),
+        "Student-t" => Box::new(StudentTNoise::new(2.5, 3.0)),
+        "Bias drift" => Box::new(NoiseChain::new(vec![
+            Box::new(BiasDriftNoise::new(0.0, 0.2)),
+            Box::new(GaussianNoise::new(4.0)),
+        ])),
+        "Quantized" => Box::new(NoiseChain::new(vec![
+            Box::new(GaussianNoise::new(4.0)),
+            Box::new(Quantization::new(5.0)),
+        ])),
+        "Outliers" => Box::new(OutlierMixture::new(
+            GaussianNoise::new(4.0),
+            UniformNoise::new(60.0),
+            0.05,
+        )),
+        _ => panic!(
+            "voltage_sensor_noise_model(): Unknown noise model {}",
+            noise_name
+        ),
+    }
+}
+
+pub fn moving_average_warm_up_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Moving average warm up example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation & data logging; the first 2 s of the textbook data
+    let num_data_pts: usize = 100;
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+
+    let mut prefill_data = Vec::<f64>::with_capacity(num_data_pts);
+    let mut grow_data = Vec::<f64>::with_capacity(num_data_pts);
+
+    // A long window makes the start up difference easy to see
+    let window_size: usize = 40;
+    let mut mv_avg_prefill =
+        MovingAverageFilter::with_warm_up(window_size, WarmUp::PrefillFirstSample);
+    let mut mv_avg_grow = MovingAverageFilter::with_warm_up(window_size, WarmUp::GrowWindow);
+
+    // Run simulation
+    for (i, &data_pt) in sonar_alt_arrs[0].iter().take(num_data_pts).enumerate() {
+        let was_full = mv_avg_grow.is_full();
+        mv_avg_prefill.update(data_pt);
+        mv_avg_grow.update(data_pt);
+
+        if mv_avg_grow.is_full() && !was_full {
+            println!("Moving average window full after {} data points", i + 1);
+        }
+
+        // Log data for plotting
+        prefill_data.push(mv_avg_prefill.get_average());
+        grow_data.push(mv_avg_grow.get_average());
+    }
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let plot_labels = PlotLabels {
+        plot_pathname: "./plots/23_MovingAverageWarmUp.png".to_string(),
+        title: "Moving Average Filter Warm Up".to_string(),
+        x_axis_label: "Time [s]".to_string(),
+        y_axis_label: "Altitude [m]".to_string(),
+        y_axis_data1_label: "Prefill first sample".to_string(),
+        y_axis_data2_label: "Grow window".to_string(),
+    };
+
+    plot_two_lines(
+        plot_labels,
+        &times_s[..num_data_pts],
+        &prefill_data,
+        &grow_data,
+    );
+}
@@ -0,0 +1,209 @@
+#![allow(non_snake_case)]
+
+use crate::recursive_filters::{Filter, RingBuffer};
+use nalgebra::{DMatrix, DVector};
+
+/// Fits with a design matrix condition number above this are rejected
+const MAX_CONDITION_NUMBER: f64 = 1e12;
+
+/// Where in the window a SavitzkyGolayFilter evaluates its fitted polynomial
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SavitzkyGolayMode {
+    /// At the newest data point; no delay, for real time use
+    Causal,
+    /// At the middle of the window; (window_size - 1) / 2 data points of delay, but less
+    /// noise and no lag on ramps, for offline use
+    Centered,
+}
+
+/// Savitzky-Golay filter: fits a polynomial to the data in a sliding window by least
+/// squares, then reads the smoothed value and the 1st and 2nd derivatives off the same fit.
+/// Each output is a fixed weighted sum of the window, with weights from the pseudo inverse
+/// of the window's polynomial design matrix J, J_ij = s_i^j, time s scaled to [-1, 1] by
+/// the half width h of the window in samples:
+/// c = J^+ z,  value = c_0,  1st derivative = c_1 / (h dt),  2nd = 2 c_2 / (h dt)^2
+/// Like MovingAverageFilter, the buffer is initialized with the first data value.
+pub struct SavitzkyGolayFilter {
+    weights: DMatrix<f64>, // rows: value, 1st derivative, 2nd derivative
+    outputs: [f64; 3],
+    window_size: usize,
+    mode: SavitzkyGolayMode,
+    data_buff: Option<RingBuffer<f64>>,
+}
+
+impl SavitzkyGolayFilter {
+    /// window_size: number of data points in the fit, odd for SavitzkyGolayMode::Centered
+    /// poly_order: polynomial order, less than window_size; derivatives above it are zero
+    /// dt: sample period [s]
+    pub fn new(window_size: usize, poly_order: usize, dt: f64, mode: SavitzkyGolayMode) -> Self {
+        check_fit(window_size, poly_order, dt);
+        assert!(
+            mode == SavitzkyGolayMode::Causal || window_size % 2 == 1,
+            "SavitzkyGolayFilter: Window size must be odd in centered mode."
+        );
+
+        let eval_idx = match mode {
+            SavitzkyGolayMode::Causal => window_size - 1,
+            SavitzkyGolayMode::Centered => (window_size - 1) / 2,
+        };
+
+        Self {
+            weights: savitzky_golay_weights(window_size, poly_order, eval_idx, dt),
+            outputs: [0.0; 3],
+            window_size,
+            mode,
+            data_buff: None,
+        }
+    }
+
+    pub fn update(&mut self, data: f64) {
+        let data_buff = self
+            .data_buff
+            .get_or_insert_with(|| RingBuffer::filled(data, self.window_size));
+        data_buff.push(data);
+
+        let window = DVector::from_iterator(self.window_size, data_buff.iter().copied());
+        let outputs = &self.weights * window;
+        self.outputs = [outputs[0], outputs[1], outputs[2]];
+    }
+
+    /// Smoothed value, get_delay() data points ago
+    pub fn get_value(&self) -> f64 {
+        self.outputs[0]
+    }
+
+    /// 1st derivative [units/s], get_delay() data points ago
+    pub fn get_first_derivative(&self) -> f64 {
+        self.outputs[1]
+    }
+
+    /// 2nd derivative [units/s^2], get_delay() data points ago
+    pub fn get_second_derivative(&self) -> f64 {
+        self.outputs[2]
+    }
+
+    /// Number of data points the outputs lag the newest data point by
+    pub fn get_delay(&self) -> usize {
+        match self.mode {
+            SavitzkyGolayMode::Causal => 0,
+            SavitzkyGolayMode::Centered => (self.window_size - 1) / 2,
+        }
+    }
+}
+
+impl Filter for SavitzkyGolayFilter {
+    fn update(&mut self, data: f64) {
+        self.update(data);
+    }
+
+    fn get_output(&self) -> f64 {
+        self.get_value()
+    }
+
+    fn reset(&mut self) {
+        self.outputs = [0.0; 3];
+        self.data_buff = None;
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.data_buff.is_some()
+    }
+}
+
+/// Centered Savitzky-Golay filter over a whole data series, without delay.
+/// The first and last (window_size - 1) / 2 data points are evaluated off the fit of the
+/// first and last full window, rather than padding the data.
+/// Returns [value, 1st derivative, 2nd derivative] for each data point.
+pub fn savitzky_golay_offline(
+    data: &[f64],
+    window_size: usize,
+    poly_order: usize,
+    dt: f64,
+) -> Vec<[f64; 3]> {
+    check_fit(window_size, poly_order, dt);
+    assert!(
+        window_size % 2 == 1 && data.len() >= window_size,
+        "savitzky_golay_offline: Window size must be odd and no longer than the data."
+    );
+
+    let half_window = (window_size - 1) / 2;
+    let weights_by_eval_idx = (0..window_size)
+        .map(|eval_idx| savitzky_golay_weights(window_size, poly_order, eval_idx, dt))
+        .collect::<Vec<_>>();
+    let last_window_start = data.len() - window_size;
+
+    (0..data.len())
+        .map(|i| {
+            let (window_start, eval_idx) = if i < half_window {
+                (0, i)
+            } else if i > last_window_start + half_window {
+                (last_window_start, i - last_window_start)
+            } else {
+                (i - half_window, half_window)
+            };
+
+            let window =
+                DVector::from_column_slice(&data[window_start..window_start + window_size]);
+            let outputs = &weights_by_eval_idx[eval_idx] * window;
+
+            [outputs[0], outputs[1], outputs[2]]
+        })
+        .collect()
+}
+
+/// Checks shared by the online and offline filters, before any window index is computed
+fn check_fit(window_size: usize, poly_order: usize, dt: f64) {
+    assert!(
+        window_size > 0,
+        "SavitzkyGolayFilter: Window size must be greater than zero."
+    );
+    assert!(
+        poly_order < window_size,
+        "SavitzkyGolayFilter: Polynomial order must be less than the window size."
+    );
+    assert!(
+        dt > 0.0,
+        "SavitzkyGolayFilter: Sample period must be greater than zero."
+    );
+}
+
+/// Weights giving the value, 1st and 2nd derivative at data point eval_idx of a window,
+/// one row each, from a least squares polynomial fit over the window
+fn savitzky_golay_weights(
+    window_size: usize,
+    poly_order: usize,
+    eval_idx: usize,
+    dt: f64,
+) -> DMatrix<f64> {
+    // Design matrix, time measured from the evaluation point and scaled to [-1, 1], which
+    // keeps the powers of time comparable in size for long windows and high orders
+    let half_width = (eval_idx.max(window_size - 1 - eval_idx) as f64).max(1.0);
+    let J = DMatrix::from_fn(window_size, poly_order + 1, |row, col| {
+        ((row as f64 - eval_idx as f64) / half_width).powi(col as i32)
+    });
+
+    let svd = J.svd(true, true);
+    let condition_number = svd.singular_values.max() / svd.singular_values.min();
+    assert!(
+        condition_number <= MAX_CONDITION_NUMBER,
+        "SavitzkyGolayFilter: Polynomial fit is ill conditioned, condition number {:.3e}.",
+        condition_number
+    );
+    let fit = svd
+        .pseudo_inverse(0.0)
+        .expect("SavitzkyGolayFilter: SVD was computed with U and V^T.");
+
+    // Derivative of order d at s = 0 is d! * c_d, scaled from [-1, 1] to seconds
+    let time_scale = half_width * dt;
+    let mut weights = DMatrix::zeros(3, window_size);
+    for (deriv_order, scale) in [1.0, 1.0 / time_scale, 2.0 / time_scale.powi(2)]
+        .into_iter()
+        .enumerate()
+    {
+        if deriv_order <= poly_order {
+            weights.set_row(deriv_order, &(fit.row(deriv_order) * scale));
+        }
+    }
+
+    weights
+}
@@ -0,0 +1,100 @@
+use crate::utils::{ascending_float_range, plot_two_lines, read_mat_file_arrays, PlotLabels};
+use kalman_filter_for_beginners_rust::kalman_filter::{KalmanFilter, SystemModel};
+use kalman_filter_for_beginners_rust::savitzky_golay_filter::{
+    savitzky_golay_offline, SavitzkyGolayFilter, SavitzkyGolayMode,
+};
+use nalgebra::{DMatrix, DVector};
+
+pub fn savitzky_golay_climb_rate_example() {
+    // Load sonar altitude simulation data
+    let Some(sonar_alt_arrs) = read_mat_file_arrays("./data/SonarAlt.mat", &["sonarAlt"]) else {
+        println!("Savitzky-Golay example skipped: ./data/SonarAlt.mat not found");
+        return;
+    };
+
+    // Setup simulation & data logging; inputs based on textbook example
+    let num_data_pts: usize = 500; // from example code
+
+    let dt: f64 = 0.02; // from example code
+    let times_s: Vec<f64> = ascending_float_range(0.0, dt * num_data_pts as f64, dt);
+    let raw_data = &sonar_alt_arrs[0][..num_data_pts];
+
+    let mut klmn_vels = Vec::<f64>::with_capacity(num_data_pts);
+    let mut causal_vels = Vec::<f64>::with_capacity(num_data_pts);
+
+    // Kalman filter set up as in kalman_filter_measure_velocity_with_sonar_example
+    const KLMN_DT: f64 = 0.1;
+    let system_model = SystemModel::new(
+        DMatrix::from_row_slice(2, 2, &[1.0, KLMN_DT, 0.0, 1.0]),
+        DMatrix::from_row_slice(2, 2, &[1.0, 0.0, 0.0, 3.0]),
+        DMatrix::from_row_slice(1, 2, &[1.0, 0.0]),
+        DMatrix::from_row_slice(1, 1, &[10.0]),
+    );
+    let mut klmn_filt = KalmanFilter::new(
+        system_model,
+        DVector::from_column_slice(&[0.0, 20.0]),
+        DMatrix::from_row_slice(2, 2, &[5.0, 0.0, 0.0, 5.0]),
+    );
+
+    // 0.5 s window, quadratic fit
+    let window_size: usize = 25;
+    let poly_order: usize = 2;
+    let mut sav_gol_filt =
+        SavitzkyGolayFilter::new(window_size, poly_order, dt, SavitzkyGolayMode::Causal);
+
+    // Run simulation
+    for &data_pt in raw_data {
+        klmn_filt.update(DVector::from_element(1, data_pt));
+        sav_gol_filt.update(data_pt);
+
+        // The textbook model uses KLMN_DT on data sampled every dt, so its velocity state is
+        // dt / KLMN_DT of the true rate; rescale to m/s
+        klmn_vels.push(klmn_filt.get_state_estimate()[1] * KLMN_DT / dt);
+        causal_vels.push(sav_gol_filt.get_first_derivative());
+    }
+
+    // Offline, centered fit over the whole recording; no delay, the best reference
+    let centered_vels: Vec<f64> = savitzky_golay_offline(raw_data, window_size, poly_order, dt)
+        .iter()
+        .map(|outputs| outputs[1])
+        .collect();
+
+    let rms_diff = |estimates: &[f64]| -> f64 {
+        (estimates
+            .iter()
+            .zip(centered_vels.iter())
+            .map(|(est, reference)| (est - reference).powi(2))
+            .sum::<f64>()
+            / num_data_pts as f64)
+            .sqrt()
+    };
+    println!(
+        "Climb rate RMS difference from centered Savitzky-Golay [m/s]: Kalman filter {:.2}, causal Savitzky-Golay {:.2}",
+        rms_diff(&klmn_vels),
+        rms_diff(&causal_vels)
+    );
+
+    // --- MAKE PLOTS --------------------------------------------------------//
+    let comparisons = [
+        ("a", "Causal Savitzky-Golay", &causal_vels),
+        ("b", "Centered Savitzky-Golay", &centered_vels),
+    ];
+
+    for (plot_suffix, filter_name, vel_estimates) in comparisons {
+        let plot_labels = PlotLabels {
+            plot_pathname: format!("./plots/24{}_SavitzkyGolay_ClimbRate.png", plot_suffix),
+            title: format!("{} Climb Rate", filter_name),
+            x_axis_label: "Time [s]".to_string(),
+            y_axis_label: "Velocity [m/s]".to_string(),
+            y_axis_data1_label: "Kalman Filter".to_string(),
+            y_axis_data2_label: filter_name.to_string(),
+        };
+
+        plot_two_lines(
+            plot_labels,
+            &times_s[..num_data_pts],
+            &klmn_vels,
+            vel_estimates,
+        );
+    }
+}
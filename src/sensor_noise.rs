@@ -0,0 +1,204 @@
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal, StudentT, Uniform};
+
+/// Error model of a simulated sensor, turning a true value into a measured one.
+/// Models may keep state between samples, e.g. a drifting bias, so each sensor owns its own.
+pub trait NoiseModel {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64;
+}
+
+impl<N: NoiseModel + ?Sized> NoiseModel for Box<N> {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        (**self).corrupt(true_value, rng)
+    }
+}
+
+/// Additive zero mean Gaussian noise, the textbook's assumption
+pub struct GaussianNoise {
+    normal: Normal<f64>,
+}
+
+impl GaussianNoise {
+    pub fn new(std_dev: f64) -> Self {
+        assert!(
+            std_dev >= 0.0,
+            "GaussianNoise: Standard deviation must not be negative."
+        );
+
+        Self {
+            normal: Normal::new(0.0, std_dev).unwrap(),
+        }
+    }
+}
+
+impl NoiseModel for GaussianNoise {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        true_value + self.normal.sample(rng)
+    }
+}
+
+/// Additive noise spread evenly over [-half_width, half_width], std dev = half_width / sqrt(3)
+pub struct UniformNoise {
+    uniform: Uniform<f64>,
+}
+
+impl UniformNoise {
+    pub fn new(half_width: f64) -> Self {
+        assert!(
+            half_width > 0.0,
+            "UniformNoise: Half width must be greater than zero."
+        );
+
+        Self {
+            uniform: Uniform::new_inclusive(-half_width, half_width),
+        }
+    }
+}
+
+impl NoiseModel for UniformNoise {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        true_value + self.uniform.sample(rng)
+    }
+}
+
+/// Additive heavy tailed noise, scale * t, where t has a Student-t distribution.
+/// Few degrees of freedom give frequent large errors; many approach GaussianNoise(scale).
+/// std dev = scale * sqrt(dof / (dof - 2)) for dof > 2, infinite otherwise
+pub struct StudentTNoise {
+    scale: f64,
+    student_t: StudentT<f64>,
+}
+
+impl StudentTNoise {
+    /// dof: degrees of freedom, greater than zero
+    pub fn new(scale: f64, dof: f64) -> Self {
+        assert!(
+            (scale > 0.0) && (dof > 0.0),
+            "StudentTNoise: Scale and degrees of freedom must be greater than zero."
+        );
+
+        Self {
+            scale,
+            student_t: StudentT::new(dof).unwrap(),
+        }
+    }
+}
+
+impl NoiseModel for StudentTNoise {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        true_value + self.scale * self.student_t.sample(rng)
+    }
+}
+
+/// Slowly drifting offset, e.g. from temperature; the bias takes a Gaussian random walk step
+/// after every sample, so its std dev grows with sqrt(number of samples)
+pub struct BiasDriftNoise {
+    bias: f64,
+    walk_step: Normal<f64>,
+}
+
+impl BiasDriftNoise {
+    /// initial_bias: offset of the first sample
+    /// walk_std: std dev of the change in bias per sample
+    pub fn new(initial_bias: f64, walk_std: f64) -> Self {
+        assert!(
+            walk_std >= 0.0,
+            "BiasDriftNoise: Random walk std dev must not be negative."
+        );
+
+        Self {
+            bias: initial_bias,
+            walk_step: Normal::new(0.0, walk_std).unwrap(),
+        }
+    }
+
+    pub fn get_bias(&self) -> f64 {
+        self.bias
+    }
+}
+
+impl NoiseModel for BiasDriftNoise {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        let measured = true_value + self.bias;
+        self.bias += self.walk_step.sample(rng);
+        measured
+    }
+}
+
+/// Rounding to the nearest multiple of a step, e.g. an ADC or a sensor's output resolution.
+/// Adds up to step / 2 of error, std dev = step / sqrt(12) for a signal that moves enough.
+pub struct Quantization {
+    step: f64,
+}
+
+impl Quantization {
+    pub fn new(step: f64) -> Self {
+        assert!(step > 0.0, "Quantization: Step must be greater than zero.");
+
+        Self { step }
+    }
+}
+
+impl NoiseModel for Quantization {
+    fn corrupt(&mut self, true_value: f64, _rng: &mut dyn RngCore) -> f64 {
+        (true_value / self.step).round() * self.step
+    }
+}
+
+/// Mostly nominal noise, with an occasional sample from the outlier model instead, e.g. a
+/// multipath echo or a bit error
+pub struct OutlierMixture {
+    nominal: Box<dyn NoiseModel>,
+    outlier: Box<dyn NoiseModel>,
+    outlier_prob: f64,
+}
+
+impl OutlierMixture {
+    /// outlier_prob: chance of each sample being an outlier, 0.0 <= outlier_prob <= 1.0
+    pub fn new(
+        nominal: impl NoiseModel + 'static,
+        outlier: impl NoiseModel + 'static,
+        outlier_prob: f64,
+    ) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&outlier_prob),
+            "OutlierMixture: Outlier probability must be between zero and one."
+        );
+
+        Self {
+            nominal: Box::new(nominal),
+            outlier: Box::new(outlier),
+            outlier_prob,
+        }
+    }
+}
+
+impl NoiseModel for OutlierMixture {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        if rng.gen_bool(self.outlier_prob) {
+            self.outlier.corrupt(true_value, rng)
+        } else {
+            self.nominal.corrupt(true_value, rng)
+        }
+    }
+}
+
+/// Several error sources applied in order, e.g. a drifting bias, then white noise, then
+/// quantization of the sum. With no models the measurement is the true value.
+pub struct NoiseChain {
+    models: Vec<Box<dyn NoiseModel>>,
+}
+
+impl NoiseChain {
+    pub fn new(models: Vec<Box<dyn NoiseModel>>) -> Self {
+        Self { models }
+    }
+}
+
+impl NoiseModel for NoiseChain {
+    fn corrupt(&mut self, true_value: f64, rng: &mut dyn RngCore) -> f64 {
+        self.models
+            .iter_mut()
+            .fold(true_value, |value, model| model.corrupt(value, rng))
+    }
+}
@@ -1,61 +1,804 @@
-use rand::thread_rng;
-use rand_distr::{Distribution, Normal};
-use std::sync::Mutex;
+use crate::sensor_noise::{GaussianNoise, NoiseModel};
+use crate::trajectory::{Segment, Trajectory, TrajectoryPoint};
+use kalman_filter_for_beginners_rust::attitude_reference_system::GRAVITY;
+use kalman_filter_for_beginners_rust::target_tracking::GrayImage;
+use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, Normal, StandardNormal};
 
-pub fn get_volt() -> f64 {
-    let mut rng = thread_rng(); // Create a random number generator
+/// One simulated sensor output with the true state that produced it, so the error of an
+/// estimate can be computed
+pub struct SensorReading<Z, X> {
+    pub measurement: Z,
+    pub truth: X,
+}
+
+/// Simulator that can be sampled at any time, so ScheduledSensor can set its rate.
+/// Samples must be taken in time order.
+pub trait TimedSensor {
+    type Measurement;
+    type Truth;
+
+    /// Measure the target at time_s [s]
+    fn measure_at(&mut self, time_s: f64) -> SensorReading<Self::Measurement, Self::Truth>;
+}
+
+// Every simulator owns its true state and random number generator, so a run is reproduced
+// by reusing the seed, and simulators never share state. R: any random number generator,
+// StdRng by default. Each starts with the textbook's Gaussian noise, which with_noise()
+// swaps for any NoiseModel.
+
+/// The default trajectories wander for this long, then hold their last velocity [s]
+const TEXTBOOK_TRUTH_DURATION_S: f64 = 3600.0;
+
+/// Constant voltage measured with Gaussian noise, based on the textbook GetVolt example
+pub struct VoltageSensor<R: Rng = StdRng> {
+    rng: R,
+    true_volt: f64,
+    noise: Box<dyn NoiseModel>,
+}
+
+impl<R: Rng> VoltageSensor<R> {
+    pub fn new(rng: R) -> Self {
+        Self {
+            rng,
+            true_volt: 14.4,
+            noise: Box::new(GaussianNoise::new(4.0)),
+        }
+    }
+
+    /// Replace the measurement noise
+    pub fn with_noise(mut self, noise: impl NoiseModel + 'static) -> Self {
+        self.noise = Box::new(noise);
+        self
+    }
+
+    pub fn measure(&mut self) -> SensorReading<f64, f64> {
+        SensorReading {
+            measurement: self.noise.corrupt(self.true_volt, &mut self.rng),
+            truth: self.true_volt,
+        }
+    }
+}
+
+impl VoltageSensor {
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> TimedSensor for VoltageSensor<R> {
+    type Measurement = f64;
+    type Truth = f64;
+
+    /// The voltage is constant, so any time gives the same truth
+    fn measure_at(&mut self, _time_s: f64) -> SensorReading<f64, f64> {
+        self.measure()
+    }
+}
+
+/// Position of a target, measured with 10 m Gaussian noise every 0.1 s, based on the textbook
+/// GetPos example. The target flies at about 80 m/s, its speed redrawn with 10 m/s Gaussian
+/// noise every step, unless with_trajectory() gives it another path.
+pub struct PositionSensor<R: Rng = StdRng> {
+    rng: R,
+    trajectory: Trajectory,
+    time_s: f64,
+    noise: Box<dyn NoiseModel>,
+}
+
+impl<R: Rng> PositionSensor<R> {
+    const DT: f64 = 0.1;
+
+    pub fn new(mut rng: R) -> Self {
+        let trajectory_seed = rng.gen();
+        Self {
+            rng,
+            trajectory: PositionSensor::textbook_trajectory(trajectory_seed),
+            time_s: 0.0,
+            noise: Box::new(GaussianNoise::new(10.0)),
+        }
+    }
+
+    /// Replace the measurement noise
+    pub fn with_noise(mut self, noise: impl NoiseModel + 'static) -> Self {
+        self.noise = Box::new(noise);
+        self
+    }
+
+    /// Replace the target's path, 1D, or the x position of a 2D or 3D path is measured
+    pub fn with_trajectory(mut self, trajectory: Trajectory) -> Self {
+        self.trajectory = trajectory;
+        self
+    }
+
+    /// The target moves one step before each measurement, so the first is at t = 0.1 s
+    pub fn measure(&mut self) -> SensorReading<f64, TrajectoryPoint> {
+        self.measure_at(self.time_s + Self::DT)
+    }
+}
+
+impl PositionSensor {
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+
+    /// The default target's path, so several sensors can watch the same target
+    pub fn textbook_trajectory(seed: u64) -> Trajectory {
+        Trajectory::new(&[0.0], &[80.0]).then(Segment::RandomVelocity {
+            duration_s: TEXTBOOK_TRUTH_DURATION_S,
+            step_s: Self::DT,
+            vel_std: vec![10.0],
+            pos_std: vec![0.0],
+            seed,
+        })
+    }
+}
+
+impl<R: Rng> TimedSensor for PositionSensor<R> {
+    type Measurement = f64;
+    type Truth = TrajectoryPoint;
+
+    fn measure_at(&mut self, time_s: f64) -> SensorReading<f64, TrajectoryPoint> {
+        self.time_s = time_s;
+        let truth = self.trajectory.state_at(time_s);
 
-    // Create a normal distribution with mean = 0 and standard deviation = 4
-    let normal = Normal::new(0.0, 4.0).unwrap();
+        SensorReading {
+            measurement: self.noise.corrupt(truth.pos[0], &mut self.rng),
+            truth,
+        }
+    }
+}
+
+/// Speed of a target every 0.1 s, based on the textbook GetVel example. The target flies at
+/// about 80 m/s, its speed redrawn with 10 m/s Gaussian noise every step, unless
+/// with_trajectory() gives it another path. As in the textbook the speed is measured exactly,
+/// it's the target that's noisy, until with_noise() adds measurement noise.
+pub struct VelocitySensor<R: Rng = StdRng> {
+    rng: R,
+    trajectory: Trajectory,
+    time_s: f64,
+    noise: Option<Box<dyn NoiseModel>>, // None: measured exactly
+}
+
+impl<R: Rng> VelocitySensor<R> {
+    const DT: f64 = 0.1;
+
+    pub fn new(mut rng: R) -> Self {
+        let trajectory_seed = rng.gen();
+        Self {
+            rng,
+            trajectory: Trajectory::new(&[0.0], &[80.0]).then(Segment::RandomVelocity {
+                duration_s: TEXTBOOK_TRUTH_DURATION_S,
+                step_s: Self::DT,
+                vel_std: vec![10.0],
+                pos_std: vec![0.0],
+                seed: trajectory_seed,
+            }),
+            time_s: 0.0,
+            noise: None,
+        }
+    }
+
+    /// Add measurement noise
+    pub fn with_noise(mut self, noise: impl NoiseModel + 'static) -> Self {
+        self.noise = Some(Box::new(noise));
+        self
+    }
+
+    /// Replace the target's path, 1D, or the x velocity of a 2D or 3D path is measured
+    pub fn with_trajectory(mut self, trajectory: Trajectory) -> Self {
+        self.trajectory = trajectory;
+        self
+    }
+
+    /// The first measurement is at t = 0 s, then the target moves one step after each
+    pub fn measure(&mut self) -> SensorReading<f64, TrajectoryPoint> {
+        let reading = self.measure_at(self.time_s);
+        self.time_s += Self::DT;
+        reading
+    }
+}
+
+impl VelocitySensor {
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> TimedSensor for VelocitySensor<R> {
+    type Measurement = f64;
+    type Truth = TrajectoryPoint;
+
+    fn measure_at(&mut self, time_s: f64) -> SensorReading<f64, TrajectoryPoint> {
+        self.time_s = time_s;
+        let truth = self.trajectory.state_at(time_s);
+
+        let measurement = match self.noise.as_mut() {
+            Some(noise) => noise.corrupt(truth.vel[0], &mut self.rng),
+            None => truth.vel[0],
+        };
+
+        SensorReading { measurement, truth }
+    }
+}
 
-    // Get a random number from the distribution
-    let w = normal.sample(&mut rng);
-    14.4 + w
+/// Slant range from a ground radar to an aircraft, based on the textbook GetRadar example.
+/// The trajectory is 2D, [horizontal distance from the radar, altitude]; by default the
+/// aircraft flies at about 1000 m and 100 m/s, its speed redrawn with 5 m/s and its altitude
+/// with 10 m Gaussian noise every 0.05 s.
+pub struct RadarSensor<R: Rng = StdRng> {
+    rng: R,
+    trajectory: Trajectory,
+    time_s: f64,
+    noise: Option<Box<dyn NoiseModel>>, // None: textbook noise, see measure()
 }
 
-static TRUE_POS_A: Mutex<f64> = Mutex::new(0.0);
-static TRUE_POS_B: Mutex<f64> = Mutex::new(0.0);
+impl<R: Rng> RadarSensor<R> {
+    pub fn new(mut rng: R) -> Self {
+        let trajectory_seed = rng.gen();
+        Self {
+            rng,
+            trajectory: Trajectory::new(&[0.0, 1000.0], &[100.0, 0.0]).then(
+                Segment::RandomVelocity {
+                    duration_s: TEXTBOOK_TRUTH_DURATION_S,
+                    step_s: 0.05,
+                    vel_std: vec![5.0, 0.0],
+                    pos_std: vec![0.0, 10.0],
+                    seed: trajectory_seed,
+                },
+            ),
+            time_s: 0.0,
+            noise: None,
+        }
+    }
+
+    /// Replace the range noise
+    pub fn with_noise(mut self, noise: impl NoiseModel + 'static) -> Self {
+        self.noise = Some(Box::new(noise));
+        self
+    }
+
+    /// Replace the aircraft's path, [horizontal distance, altitude]
+    pub fn with_trajectory(mut self, trajectory: Trajectory) -> Self {
+        assert!(
+            trajectory.get_num_dims() == 2,
+            "RadarSensor: Trajectory must be 2D, [horizontal distance, altitude]."
+        );
+
+        self.trajectory = trajectory;
+        self
+    }
 
-pub static TRUE_VEL_A: Mutex<f64> = Mutex::new(80.0);
-static TRUE_VEL_B: Mutex<f64> = Mutex::new(80.0);
+    /// dt: time since the last measurement [s]
+    pub fn measure(&mut self, dt: f64) -> SensorReading<f64, TrajectoryPoint> {
+        self.measure_at(self.time_s + dt)
+    }
+}
 
-const DT: f64 = 0.1;
+impl RadarSensor {
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
 
-pub fn get_position() -> f64 {
-    // Create a random number generator
-    let mut rng = thread_rng();
-    // Create a normal distribution with mean = 0 and standard deviation = 1
-    let normal = Normal::new(0.0, 1.0).unwrap();
+impl<R: Rng> TimedSensor for RadarSensor<R> {
+    type Measurement = f64;
+    type Truth = TrajectoryPoint;
 
-    let w: f64 = 0.0 + 10.0 * normal.sample(&mut rng);
-    let v: f64 = 0.0 + 10.0 * normal.sample(&mut rng);
+    fn measure_at(&mut self, time_s: f64) -> SensorReading<f64, TrajectoryPoint> {
+        self.time_s = time_s;
+        let truth = self.trajectory.state_at(time_s);
+        let (true_dist, true_alt) = (truth.pos[0], truth.pos[1]);
 
-    let mut true_pos = TRUE_POS_A.lock().unwrap();
-    let mut true_vel = TRUE_VEL_A.lock().unwrap();
+        let true_range = (true_dist.powi(2) + true_alt.powi(2)).sqrt();
+        let measurement = match self.noise.as_mut() {
+            Some(noise) => noise.corrupt(true_range, &mut self.rng),
+            // Textbook noise grows with distance from the radar, 5% of the horizontal distance
+            None => true_range + true_dist * 0.05 * self.rng.sample::<f64, _>(StandardNormal),
+        };
 
-    let z: f64 = *true_pos + *true_vel * DT + v;
+        SensorReading { measurement, truth }
+    }
+}
 
-    *true_pos = z - v; // true position
-    *true_vel = 80.0 + w; // true speed
+pub const BLOB_IMAGE_WIDTH: usize = 160;
+pub const BLOB_IMAGE_HEIGHT: usize = 120;
 
-    return z;
+/// Camera frames of a bright blob moving at constant velocity and bouncing off the image
+/// edges, on a dark background with Gaussian pixel noise. Stands in for the textbook ball
+/// images. The truth is the blob center [x, y] in pixels.
+pub struct BlobCamera<R: Rng = StdRng> {
+    rng: R,
+    true_pos: [f64; 2],
+    true_vel: [f64; 2],
+    pixel_noise: Box<dyn NoiseModel>,
 }
 
-pub fn get_velocity() -> f64 {
-    // Create a random number generator
-    let mut rng = thread_rng();
-    // Create a normal distribution with mean = 0 and standard deviation = 1
-    let normal = Normal::new(0.0, 1.0).unwrap();
+impl<R: Rng> BlobCamera<R> {
+    pub fn new(rng: R) -> Self {
+        Self {
+            rng,
+            true_pos: [20.0, 20.0],
+            true_vel: [3.0, 2.0],
+            pixel_noise: Box::new(GaussianNoise::new(0.1)),
+        }
+    }
+
+    /// Replace the noise added to every pixel
+    pub fn with_noise(mut self, noise: impl NoiseModel + 'static) -> Self {
+        self.pixel_noise = Box::new(noise);
+        self
+    }
 
-    let v: f64 = 0.0 + 10.0 * normal.sample(&mut rng);
+    pub fn capture(&mut self) -> SensorReading<GrayImage, [f64; 2]> {
+        // Move the blob, bouncing off the edges of the image
+        let image_size = [BLOB_IMAGE_WIDTH as f64, BLOB_IMAGE_HEIGHT as f64];
+        for ((pos, vel), size) in self
+            .true_pos
+            .iter_mut()
+            .zip(self.true_vel.iter_mut())
+            .zip(image_size)
+        {
+            *pos += *vel;
+            if *pos < 0.0 || *pos > size - 1.0 {
+                *vel = -*vel;
+                *pos = pos.clamp(0.0, size - 1.0);
+            }
+        }
+
+        // Render the blob as a Gaussian spot, radius ~ 2 * sigma
+        const BLOB_SIGMA_PX: f64 = 3.0;
+        let mut image = GrayImage::new(BLOB_IMAGE_WIDTH, BLOB_IMAGE_HEIGHT);
+        for row in 0..BLOB_IMAGE_HEIGHT {
+            for col in 0..BLOB_IMAGE_WIDTH {
+                let dist_sq = (col as f64 - self.true_pos[0]).powi(2)
+                    + (row as f64 - self.true_pos[1]).powi(2);
+                let blob = (-dist_sq / (2.0 * BLOB_SIGMA_PX.powi(2))).exp();
+                image.set(col, row, self.pixel_noise.corrupt(blob, &mut self.rng));
+            }
+        }
+
+        SensorReading {
+            measurement: image,
+            truth: self.true_pos,
+        }
+    }
+}
+
+impl BlobCamera {
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+/// Error budget of one 3-axis inertial sensor, a gyro or an accelerometer, applied as
+/// measured = (I + diag(scale_factors) + misalignment) * true + bias + white noise
+#[derive(Clone, Debug)]
+pub struct InertialSensorErrors {
+    bias: Vector3<f64>,
+    bias_walk_std: f64,
+    scale_factors: Vector3<f64>,
+    misalignment: Matrix3<f64>,
+    noise: Normal<f64>,
+}
 
-    let mut true_pos = TRUE_POS_B.lock().unwrap();
-    let mut true_vel = TRUE_VEL_B.lock().unwrap();
+impl InertialSensorErrors {
+    /// White noise only, noise_std: standard deviation per sample
+    pub fn new(noise_std: f64) -> Self {
+        assert!(
+            noise_std >= 0.0,
+            "InertialSensorErrors: Noise standard deviation must not be negative."
+        );
 
-    let z: f64 = 80.0 + v;
+        Self {
+            bias: Vector3::zeros(),
+            bias_walk_std: 0.0,
+            scale_factors: Vector3::zeros(),
+            misalignment: Matrix3::zeros(),
+            noise: Normal::new(0.0, noise_std).unwrap(),
+        }
+    }
 
-    *true_pos = *true_pos + *true_vel * DT; // true position
-    *true_vel = z; // true speed
+    /// initial_bias: turn-on bias of each axis
+    /// bias_walk_std: bias instability, standard deviation of the bias random walk per sample
+    pub fn with_bias(mut self, initial_bias: Vector3<f64>, bias_walk_std: f64) -> Self {
+        assert!(
+            bias_walk_std >= 0.0,
+            "InertialSensorErrors: Bias random walk std dev must not be negative."
+        );
+
+        self.bias = initial_bias;
+        self.bias_walk_std = bias_walk_std;
+        self
+    }
+
+    /// Fractional gain error of each axis, e.g. 0.01 reads 1% high
+    pub fn with_scale_factors(mut self, scale_factors: Vector3<f64>) -> Self {
+        self.scale_factors = scale_factors;
+        self
+    }
+
+    /// Cross axis coupling from sensing axes that aren't quite along the body axes, small
+    /// angles [rad]; entry (i, j) is how much axis i senses along body axis j.
+    /// The diagonal must be zero, gain errors are scale factors.
+    pub fn with_misalignment(mut self, misalignment: Matrix3<f64>) -> Self {
+        assert!(
+            misalignment.diagonal().iter().all(|&m| m == 0.0),
+            "InertialSensorErrors: Misalignment diagonal must be zero."
+        );
+
+        self.misalignment = misalignment;
+        self
+    }
+
+    /// Current bias of each axis
+    pub fn get_bias(&self) -> Vector3<f64> {
+        self.bias
+    }
+
+    /// Measure the true value, then take the bias's random walk step
+    fn corrupt<R: Rng>(&mut self, true_value: &Vector3<f64>, rng: &mut R) -> Vector3<f64> {
+        let gain = Matrix3::identity() + Matrix3::from_diagonal(&self.scale_factors);
+        let white_noise = Vector3::from_fn(|_, _| self.noise.sample(rng));
+        let measured = (gain + self.misalignment) * true_value + self.bias + white_noise;
+
+        self.bias +=
+            Vector3::from_fn(|_, _| self.bias_walk_std * rng.sample::<f64, _>(StandardNormal));
+        measured
+    }
+}
+
+/// Gyro and accelerometer samples, in the body frame
+#[derive(Clone, Copy, Debug)]
+pub struct ImuSample {
+    /// Body rates p, q, r [rad/s]
+    pub gyro: Vector3<f64>,
+    /// Specific force [m/s^2]
+    pub accel: Vector3<f64>,
+}
+
+/// True attitude, motion and sensor biases behind an ImuSample
+#[derive(Clone, Debug)]
+pub struct ImuTruth {
+    pub time_s: f64,
+    /// [roll, pitch, yaw] [rad]
+    pub euler_angles: Vector3<f64>,
+    pub quaternion: UnitQuaternion<f64>,
+    /// Position, velocity and acceleration in the navigation frame, [north, east, down]
+    pub motion: TrajectoryPoint,
+    /// Error free gyro and accelerometer samples
+    pub ideal_sample: ImuSample,
+    pub gyro_bias: Vector3<f64>,
+    pub accel_bias: Vector3<f64>,
+}
+
+/// Strapdown 3-axis gyro and accelerometer, standing in for the textbook ArsGyro and ArsAccel
+/// recordings. The attitude is a 3D trajectory of the Euler angles [roll, pitch, yaw] in
+/// radians, so its velocity is the Euler angle rates. The motion is a 3D trajectory in the
+/// north, east, down navigation frame, gravity along +z, as in AttitudeErrorStateKalmanFilter.
+/// Defaults to level and stationary, with a little white noise on each sensor.
+pub struct ImuSensor<R: Rng = StdRng> {
+    rng: R,
+    dt: f64,
+    time_s: f64,
+    attitude: Trajectory,
+    motion: Trajectory,
+    gyro_errors: InertialSensorErrors,
+    accel_errors: InertialSensorErrors,
+}
+
+impl<R: Rng> ImuSensor<R> {
+    /// dt: sample period [s]
+    pub fn new(rng: R, dt: f64) -> Self {
+        assert!(
+            dt > 0.0,
+            "ImuSensor: Sample period must be greater than zero."
+        );
+
+        Self {
+            rng,
+            dt,
+            time_s: 0.0,
+            attitude: Trajectory::new(&[0.0, 0.0, 0.0], &[0.0, 0.0, 0.0]),
+            motion: Trajectory::new(&[0.0, 0.0, 0.0], &[0.0, 0.0, 0.0]),
+            gyro_errors: InertialSensorErrors::new(0.005),
+            accel_errors: InertialSensorErrors::new(0.05),
+        }
+    }
+
+    /// Replace the Euler angle history, [roll, pitch, yaw] [rad]
+    pub fn with_attitude(mut self, attitude: Trajectory) -> Self {
+        assert!(
+            attitude.get_num_dims() == 3,
+            "ImuSensor: Attitude trajectory must be 3D, [roll, pitch, yaw]."
+        );
+
+        self.attitude = attitude;
+        self
+    }
+
+    /// Replace the path, [north, east, down] [m]
+    pub fn with_motion(mut self, motion: Trajectory) -> Self {
+        assert!(
+            motion.get_num_dims() == 3,
+            "ImuSensor: Motion trajectory must be 3D, [north, east, down]."
+        );
+
+        self.motion = motion;
+        self
+    }
+
+    /// Replace the gyro errors [rad/s]
+    pub fn with_gyro_errors(mut self, gyro_errors: InertialSensorErrors) -> Self {
+        self.gyro_errors = gyro_errors;
+        self
+    }
+
+    /// Replace the accelerometer errors [m/s^2]
+    pub fn with_accel_errors(mut self, accel_errors: InertialSensorErrors) -> Self {
+        self.accel_errors = accel_errors;
+        self
+    }
+
+    /// The first sample is at t = 0 s, then time moves on by dt after each
+    pub fn measure(&mut self) -> SensorReading<ImuSample, ImuTruth> {
+        let reading = self.measure_at(self.time_s);
+        self.time_s += self.dt;
+        reading
+    }
+}
+
+impl ImuSensor {
+    pub fn from_seed(seed: u64, dt: f64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed), dt)
+    }
+}
+
+impl<R: Rng> TimedSensor for ImuSensor<R> {
+    type Measurement = ImuSample;
+    type Truth = ImuTruth;
+
+    /// The biases take one random walk step per sample, whatever the time between samples
+    fn measure_at(&mut self, time_s: f64) -> SensorReading<ImuSample, ImuTruth> {
+        self.time_s = time_s;
+        let euler = self.attitude.state_at(time_s);
+        let motion = self.motion.state_at(time_s);
+
+        let (phi, theta, psi) = (euler.pos[0], euler.pos[1], euler.pos[2]);
+        let (phi_dot, theta_dot, psi_dot) = (euler.vel[0], euler.vel[1], euler.vel[2]);
+        let quaternion = UnitQuaternion::from_euler_angles(phi, theta, psi);
+
+        // Body rates from Euler angle rates, the inverse of the Chapter 12 kinematics
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let true_gyro = Vector3::new(
+            phi_dot - psi_dot * sin_theta,
+            theta_dot * cos_phi + psi_dot * sin_phi * cos_theta,
+            -theta_dot * sin_phi + psi_dot * cos_phi * cos_theta,
+        );
+
+        // The accelerometer senses acceleration minus gravity, rotated into the body frame
+        let nav_accel = Vector3::new(motion.acc[0], motion.acc[1], motion.acc[2]);
+        let true_accel = quaternion.inverse() * (nav_accel - Vector3::new(0.0, 0.0, GRAVITY));
+
+        let truth = ImuTruth {
+            time_s,
+            euler_angles: Vector3::new(phi, theta, psi),
+            quaternion,
+            motion,
+            ideal_sample: ImuSample {
+                gyro: true_gyro,
+                accel: true_accel,
+            },
+            gyro_bias: self.gyro_errors.get_bias(),
+            accel_bias: self.accel_errors.get_bias(),
+        };
+        let measurement = ImuSample {
+            gyro: self.gyro_errors.corrupt(&true_gyro, &mut self.rng),
+            accel: self.accel_errors.corrupt(&true_accel, &mut self.rng),
+        };
+
+        SensorReading { measurement, truth }
+    }
+}
+
+/// Measurement as received, stamped with when it was taken and when it arrived
+pub struct TimestampedReading<Z, X> {
+    pub sample_time_s: f64,
+    pub arrival_time_s: f64,
+    pub measurement: Z,
+    pub truth: X,
+}
+
+/// Delay between taking a measurement and receiving it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Latency {
+    None,
+    Fixed {
+        delay_s: f64,
+    },
+    /// Spread evenly between min_delay_s and max_delay_s, so late measurements can arrive
+    /// after ones taken later
+    Uniform {
+        min_delay_s: f64,
+        max_delay_s: f64,
+    },
+}
+
+/// Samples a TimedSensor at its own rate and delivers the measurements over an unreliable
+/// link: each can be dropped, the link can go down for a while, and measurements arrive late,
+/// possibly out of order. Several of these, one per sensor, simulate a multi-rate system.
+///
+/// let mut position_link = ScheduledSensor::from_seed(PositionSensor::from_seed(1), 0.1, 2)
+///     .with_dropout(0.05)
+///     .with_latency(Latency::Fixed { delay_s: 0.2 });
+/// for reading in position_link.advance_to(time_s) { ... }
+pub struct ScheduledSensor<S: TimedSensor, R: Rng = StdRng> {
+    sensor: S,
+    rng: R,
+    sample_period_s: f64,
+    start_time_s: f64,
+    next_sample_idx: u64,
+    dropout_prob: f64,
+    outage_prob: f64,
+    outage_duration: Option<Exp<f64>>,
+    outage_end_time_s: f64,
+    latency: Latency,
+    in_transit: Vec<TimestampedReading<S::Measurement, S::Truth>>,
+    num_samples: usize,
+    num_lost: usize,
+}
+
+impl<S: TimedSensor, R: Rng> ScheduledSensor<S, R> {
+    /// sample_period_s: time between samples [s], the first is at t = 0 s
+    /// rng: draws the losses and delays, separate from the sensor's own noise
+    pub fn new(sensor: S, sample_period_s: f64, rng: R) -> Self {
+        assert!(
+            sample_period_s > 0.0,
+            "ScheduledSensor: Sample period must be greater than zero."
+        );
+
+        Self {
+            sensor,
+            rng,
+            sample_period_s,
+            start_time_s: 0.0,
+            next_sample_idx: 0,
+            dropout_prob: 0.0,
+            outage_prob: 0.0,
+            outage_duration: None,
+            outage_end_time_s: f64::NEG_INFINITY,
+            latency: Latency::None,
+            in_transit: Vec::new(),
+            num_samples: 0,
+            num_lost: 0,
+        }
+    }
+
+    /// Take the first sample at start_time_s instead, e.g. to stagger sensors [s]
+    pub fn with_start_time(mut self, start_time_s: f64) -> Self {
+        self.start_time_s = start_time_s;
+        self
+    }
+
+    /// Chance of losing each measurement on its own, 0.0 <= dropout_prob <= 1.0
+    pub fn with_dropout(mut self, dropout_prob: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&dropout_prob),
+            "ScheduledSensor: Dropout probability must be between zero and one."
+        );
+
+        self.dropout_prob = dropout_prob;
+        self
+    }
+
+    /// Burst outages, losing every measurement until the link comes back.
+    /// outage_prob: chance of an outage starting at each sample, 0.0 <= outage_prob <= 1.0
+    /// mean_duration_s: outage lengths are exponentially distributed with this mean [s]
+    pub fn with_burst_outages(mut self, outage_prob: f64, mean_duration_s: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&outage_prob) && (mean_duration_s > 0.0),
+            "ScheduledSensor: Outage probability must be between zero and one, \
+             and mean duration greater than zero."
+        );
+
+        self.outage_prob = outage_prob;
+        self.outage_duration = Some(Exp::new(1.0 / mean_duration_s).unwrap());
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Latency) -> Self {
+        match latency {
+            Latency::None => {}
+            Latency::Fixed { delay_s } => assert!(
+                delay_s >= 0.0,
+                "ScheduledSensor: Latency must not be negative."
+            ),
+            Latency::Uniform {
+                min_delay_s,
+                max_delay_s,
+            } => assert!(
+                (min_delay_s >= 0.0) && (max_delay_s >= min_delay_s),
+                "ScheduledSensor: Latency must not be negative, and max must not be less than min."
+            ),
+        }
+
+        self.latency = latency;
+        self
+    }
+
+    /// Take every sample due up to time_s, and return the measurements that have arrived by
+    /// then, in arrival order. Lost measurements are sampled too, so the sensor's noise
+    /// doesn't depend on the link.
+    pub fn advance_to(&mut self, time_s: f64) -> Vec<TimestampedReading<S::Measurement, S::Truth>> {
+        loop {
+            let sample_time_s =
+                self.start_time_s + self.next_sample_idx as f64 * self.sample_period_s;
+            if sample_time_s > time_s {
+                break;
+            }
+            self.next_sample_idx += 1;
+
+            let reading = self.sensor.measure_at(sample_time_s);
+            self.num_samples += 1;
+
+            if let Some(outage_duration) = self.outage_duration {
+                if (sample_time_s >= self.outage_end_time_s) && self.rng.gen_bool(self.outage_prob)
+                {
+                    self.outage_end_time_s = sample_time_s + outage_duration.sample(&mut self.rng);
+                }
+            }
+            if (sample_time_s < self.outage_end_time_s) || self.rng.gen_bool(self.dropout_prob) {
+                self.num_lost += 1;
+                continue;
+            }
+
+            let delay_s = match self.latency {
+                Latency::None => 0.0,
+                Latency::Fixed { delay_s } => delay_s,
+                Latency::Uniform {
+                    min_delay_s,
+                    max_delay_s,
+                } => self.rng.gen_range(min_delay_s..=max_delay_s),
+            };
+            self.in_transit.push(TimestampedReading {
+                sample_time_s,
+                arrival_time_s: sample_time_s + delay_s,
+                measurement: reading.measurement,
+                truth: reading.truth,
+            });
+        }
+
+        let (mut arrived, in_transit): (Vec<_>, Vec<_>) = self
+            .in_transit
+            .drain(..)
+            .partition(|reading| reading.arrival_time_s <= time_s);
+        self.in_transit = in_transit;
+
+        // Stable, so measurements arriving together stay in sample order
+        arrived.sort_by(|a, b| a.arrival_time_s.total_cmp(&b.arrival_time_s));
+        arrived
+    }
+
+    pub fn get_sensor(&self) -> &S {
+        &self.sensor
+    }
+
+    /// Number of samples taken, including lost ones
+    pub fn get_num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// Number of samples lost to dropouts and outages
+    pub fn get_num_lost(&self) -> usize {
+        self.num_lost
+    }
+}
 
-    return z;
+impl<S: TimedSensor> ScheduledSensor<S> {
+    pub fn from_seed(sensor: S, sample_period_s: f64, seed: u64) -> Self {
+        Self::new(sensor, sample_period_s, StdRng::seed_from_u64(seed))
+    }
 }
@@ -0,0 +1,80 @@
+#![allow(non_snake_case)]
+
+use crate::kalman_filter::SystemModel;
+use nalgebra::DMatrix;
+
+/// Grayscale image, intensities stored row by row, (0, 0) is the top left pixel
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<f64>,
+}
+
+impl GrayImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0.0; width * height],
+        }
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> f64 {
+        self.pixels[row * self.width + col]
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, intensity: f64) {
+        self.pixels[row * self.width + col] = intensity;
+    }
+}
+
+/// Intensity weighted centroid of the pixels brighter than threshold, Chapter 10
+/// Returns [x, y] in pixels, or None if no pixel is above the threshold.
+pub fn detect_centroid(image: &GrayImage, threshold: f64) -> Option<[f64; 2]> {
+    let mut total_intensity = 0.0;
+    let mut sum_x = 0.0;
+    let mut sum_y = 0.0;
+
+    for row in 0..image.height {
+        for col in 0..image.width {
+            let intensity = image.get(col, row);
+            if intensity > threshold {
+                total_intensity += intensity;
+                sum_x += intensity * col as f64;
+                sum_y += intensity * row as f64;
+            }
+        }
+    }
+
+    if total_intensity > 0.0 {
+        Some([sum_x / total_intensity, sum_y / total_intensity])
+    } else {
+        None
+    }
+}
+
+/// 2D constant velocity model, Chapter 10, pg. 98
+/// State x = [pos x, vel x, pos y, vel y], measurement z = [pos x, pos y]
+/// dt: sample period
+/// prcs_var: process noise variance, Q = prcs_var * I
+/// meas_var: measurement noise variance, R = meas_var * I
+pub fn constant_velocity_2d_system_model(dt: f64, prcs_var: f64, meas_var: f64) -> SystemModel {
+    let A = DMatrix::from_row_slice(
+        4,
+        4,
+        &[
+            1.0, dt, 0.0, 0.0, //
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 1.0, dt, //
+            0.0, 0.0, 0.0, 1.0,
+        ],
+    );
+    let H = DMatrix::from_row_slice(2, 4, &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+    SystemModel::new(
+        A,
+        DMatrix::identity(4, 4) * prcs_var,
+        H,
+        DMatrix::identity(2, 2) * meas_var,
+    )
+}
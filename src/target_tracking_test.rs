@@ -0,0 +1,115 @@
+use crate::sensor_spoofs::{BlobCamera, BLOB_IMAGE_HEIGHT, BLOB_IMAGE_WIDTH};
+use kalman_filter_for_beginners_rust::kalman_filter::KalmanFilter;
+use kalman_filter_for_beginners_rust::target_tracking::{
+    constant_velocity_2d_system_model, detect_centroid,
+};
+use nalgebra::{DMatrix, DVector};
+use plotters::prelude::*;
+
+pub fn kalman_filter_image_tracking_example() {
+    // Setup simulation; inputs based on textbook example, one sample per frame
+    let num_frames: usize = 80;
+    let detection_threshold: f64 = 0.6;
+
+    // Initialize system model, state x = [pos x, vel x, pos y, vel y]
+    let system_model = constant_velocity_2d_system_model(1.0, 1.0, 50.0);
+
+    // Initialize Kalman filter
+    let mut klmn_filt = KalmanFilter::new(
+        system_model,
+        DVector::zeros(4),
+        DMatrix::identity(4, 4) * 100.0,
+    );
+
+    // Each frame is drawn at 4x scale into an animated GIF, 100 ms per frame
+    const PX_SCALE: i32 = 4;
+    let plot_pathname = "./plots/15_KalmanFilter_ImageTracking.gif";
+    let root = BitMapBackend::gif(
+        plot_pathname,
+        (
+            BLOB_IMAGE_WIDTH as u32 * PX_SCALE as u32,
+            BLOB_IMAGE_HEIGHT as u32 * PX_SCALE as u32,
+        ),
+        100,
+    )
+    .expect("BitMapBackend::gif() failed")
+    .into_drawing_area();
+
+    let to_screen = |[x, y]: [f64; 2]| -> (i32, i32) {
+        (
+            (x * PX_SCALE as f64) as i32 + PX_SCALE / 2,
+            (y * PX_SCALE as f64) as i32 + PX_SCALE / 2,
+        )
+    };
+
+    let mut estimate_track = Vec::<(i32, i32)>::with_capacity(num_frames);
+    let mut sum_sq_error = 0.0;
+
+    let mut camera = BlobCamera::from_seed(1);
+
+    // Run simulation
+    for _ in 0..num_frames {
+        let reading = camera.capture();
+        let image = reading.measurement;
+        let detection = detect_centroid(&image, detection_threshold);
+
+        // A frame without a detection still moves the estimate on by one frame
+        match detection {
+            Some(centroid) => klmn_filt.update(DVector::from_column_slice(&centroid)),
+            None => klmn_filt.update_without_measurement(),
+        }
+
+        let est_x = klmn_filt.get_state_estimate();
+        let est_pos = [est_x[0], est_x[2]];
+        let true_pos = reading.truth;
+        sum_sq_error += (est_pos[0] - true_pos[0]).powi(2) + (est_pos[1] - true_pos[1]).powi(2);
+        estimate_track.push(to_screen(est_pos));
+
+        // --- DRAW FRAME ----------------------------------------------------//
+        let _ = root.fill(&BLACK);
+
+        // Camera image in grayscale
+        for row in 0..image.height {
+            for col in 0..image.width {
+                let level = (image.get(col, row).clamp(0.0, 1.0) * 255.0) as u8;
+                let top_left = (col as i32 * PX_SCALE, row as i32 * PX_SCALE);
+                let bottom_right = (top_left.0 + PX_SCALE, top_left.1 + PX_SCALE);
+                let _ = root.draw(&Rectangle::new(
+                    [top_left, bottom_right],
+                    RGBColor(level, level, level).filled(),
+                ));
+            }
+        }
+
+        // Kalman filter track as a blue line, current estimate as a blue circle
+        let _ = root.draw(&PathElement::new(
+            estimate_track.clone(),
+            BLUE.stroke_width(2),
+        ));
+        let _ = root.draw(&Circle::new(
+            to_screen(est_pos),
+            6 * PX_SCALE,
+            BLUE.stroke_width(2),
+        ));
+
+        // Centroid detection as a red cross
+        if let Some(centroid) = detection {
+            let _ = root.draw(&Cross::new(
+                to_screen(centroid),
+                3 * PX_SCALE,
+                RED.stroke_width(2),
+            ));
+        }
+
+        root.present().expect("present() GIF frame failed");
+    }
+
+    println!(
+        "Kalman filter image tracking RMS position error: {:.2} px",
+        (sum_sq_error / num_frames as f64).sqrt()
+    );
+    println!(
+        "Kalman filter image tracking animation written: {}",
+        plot_pathname
+    );
+}
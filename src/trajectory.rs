@@ -0,0 +1,371 @@
+use nalgebra::DVector;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use std::f64::consts::PI;
+
+/// True motion at one instant, in 1, 2 or 3 dimensions
+#[derive(Clone, Debug)]
+pub struct TrajectoryPoint {
+    pub time_s: f64,
+    pub pos: DVector<f64>,
+    pub vel: DVector<f64>,
+    pub acc: DVector<f64>,
+}
+
+/// One step_s interval of a RandomVelocity segment, drawn once when the segment is added
+#[derive(Clone, Debug)]
+struct RandomStep {
+    pos: DVector<f64>, // at the start of the step, without pos noise
+    vel: DVector<f64>,
+    pos_noise: DVector<f64>,
+}
+
+/// One manoeuvre of a trajectory, starting from where the previous one ended
+#[derive(Clone, Debug)]
+pub enum Segment {
+    /// Hold the current velocity
+    ConstantVelocity { duration_s: f64 },
+    /// Accelerate steadily, acc has one element per dimension [m/s^2]
+    ConstantAcceleration { duration_s: f64, acc: Vec<f64> },
+    /// Turn at a constant rate and speed in the x-y plane, positive is counterclockwise;
+    /// any z velocity is held. 2D and 3D only.
+    CoordinatedTurn {
+        duration_s: f64,
+        turn_rate_rad_s: f64,
+    },
+    /// Weave off the constant velocity path, pos offset = amplitude * (1 - cos(2 pi f t)), so
+    /// the velocity is continuous and returns to the start velocity after whole cycles
+    Sinusoid {
+        duration_s: f64,
+        amplitude: Vec<f64>,
+        freq_hz: f64,
+    },
+    /// Change velocity instantly at the start of the segment, then hold it
+    VelocityStep {
+        duration_s: f64,
+        delta_vel: Vec<f64>,
+    },
+    /// The textbook's process noise: every step_s the velocity is redrawn as the start
+    /// velocity plus white noise, and the position integrates it. pos_std adds white noise to
+    /// the position alone, as the textbook does to the radar altitude. Both have one element
+    /// per dimension. The draws come from seed, so every sensor given this trajectory sees the
+    /// same target.
+    RandomVelocity {
+        duration_s: f64,
+        step_s: f64,
+        vel_std: Vec<f64>,
+        pos_std: Vec<f64>,
+        seed: u64,
+    },
+}
+
+impl Segment {
+    fn get_duration_s(&self) -> f64 {
+        match *self {
+            Self::ConstantVelocity { duration_s }
+            | Self::ConstantAcceleration { duration_s, .. }
+            | Self::CoordinatedTurn { duration_s, .. }
+            | Self::Sinusoid { duration_s, .. }
+            | Self::VelocityStep { duration_s, .. }
+            | Self::RandomVelocity { duration_s, .. } => duration_s,
+        }
+    }
+
+    /// Steps of a RandomVelocity segment from its start position and velocity, so sampling
+    /// indexes into them rather than replaying the draws; empty for other segments
+    fn draw_random_steps(
+        &self,
+        start_pos: &DVector<f64>,
+        start_vel: &DVector<f64>,
+    ) -> Vec<RandomStep> {
+        let Self::RandomVelocity {
+            duration_s,
+            step_s,
+            vel_std,
+            pos_std,
+            seed,
+        } = self
+        else {
+            return Vec::new();
+        };
+
+        let num_dims = start_pos.len();
+        let mut rng = StdRng::seed_from_u64(*seed);
+        let mut noise = |std: &Vec<f64>| {
+            DVector::from_iterator(
+                num_dims,
+                std.iter()
+                    .map(|std| std * rng.sample::<f64, _>(StandardNormal)),
+            )
+        };
+
+        // One step past the end, so a sample at duration_s has a step to index
+        let num_steps = step_index(*duration_s, *step_s) + 1;
+        let mut pos = start_pos.clone();
+        let mut random_steps = Vec::with_capacity(num_steps);
+        for _ in 0..num_steps {
+            let vel = start_vel + noise(vel_std);
+            let pos_noise = noise(pos_std);
+            let next_pos = &pos + &vel * *step_s;
+            random_steps.push(RandomStep {
+                pos,
+                vel,
+                pos_noise,
+            });
+            pos = next_pos;
+        }
+        random_steps
+    }
+
+    /// Motion tau seconds into the segment, from the position and velocity it starts with.
+    /// random_steps: from draw_random_steps for the same start
+    fn evaluate(
+        &self,
+        start_pos: &DVector<f64>,
+        start_vel: &DVector<f64>,
+        random_steps: &[RandomStep],
+        tau: f64,
+    ) -> [DVector<f64>; 3] {
+        let num_dims = start_pos.len();
+        let zeros = DVector::zeros(num_dims);
+
+        match self {
+            Self::ConstantVelocity { .. } => {
+                [start_pos + start_vel * tau, start_vel.clone(), zeros]
+            }
+            Self::ConstantAcceleration { acc, .. } => {
+                let acc = DVector::from_column_slice(acc);
+                [
+                    start_pos + start_vel * tau + &acc * (0.5 * tau.powi(2)),
+                    start_vel + &acc * tau,
+                    acc,
+                ]
+            }
+            Self::CoordinatedTurn {
+                turn_rate_rad_s, ..
+            } => {
+                let omega = *turn_rate_rad_s;
+                let (sin, cos) = (omega * tau).sin_cos();
+                let (vx, vy) = (start_vel[0], start_vel[1]);
+
+                // Integral of the rotated velocity; a straight line as the turn rate goes to 0
+                let (sin_over_omega, one_minus_cos_over_omega) = if omega.abs() < 1e-9 {
+                    (tau, 0.0)
+                } else {
+                    (sin / omega, (1.0 - cos) / omega)
+                };
+
+                let mut pos = start_pos + start_vel * tau;
+                pos[0] = start_pos[0] + sin_over_omega * vx - one_minus_cos_over_omega * vy;
+                pos[1] = start_pos[1] + one_minus_cos_over_omega * vx + sin_over_omega * vy;
+
+                let mut vel = start_vel.clone();
+                vel[0] = cos * vx - sin * vy;
+                vel[1] = sin * vx + cos * vy;
+
+                let mut acc = zeros;
+                acc[0] = -omega * vel[1];
+                acc[1] = omega * vel[0];
+
+                [pos, vel, acc]
+            }
+            Self::Sinusoid {
+                amplitude, freq_hz, ..
+            } => {
+                let amplitude = DVector::from_column_slice(amplitude);
+                let omega = 2.0 * PI * freq_hz;
+                let (sin, cos) = (omega * tau).sin_cos();
+                [
+                    start_pos + start_vel * tau + &amplitude * (1.0 - cos),
+                    start_vel + &amplitude * (omega * sin),
+                    &amplitude * (omega.powi(2) * cos),
+                ]
+            }
+            Self::VelocityStep { delta_vel, .. } => {
+                let vel = start_vel + DVector::from_column_slice(delta_vel);
+                [start_pos + &vel * tau, vel, zeros]
+            }
+            Self::RandomVelocity { step_s, .. } => {
+                let step_idx = step_index(tau, *step_s).min(random_steps.len() - 1);
+                let step = &random_steps[step_idx];
+                let pos = &step.pos + &step.vel * (tau - step_idx as f64 * step_s).max(0.0);
+                [pos + &step.pos_noise, step.vel.clone(), zeros]
+            }
+        }
+    }
+}
+
+/// Index of the step_s interval containing tau; the tolerance keeps a sample taken on a step
+/// boundary in the step it starts
+fn step_index(tau: f64, step_s: f64) -> usize {
+    (tau / step_s + 1e-6).floor() as usize
+}
+
+/// Start of a segment, cached as segments are added
+#[derive(Clone, Debug)]
+struct SegmentStart {
+    time_s: f64,
+    pos: DVector<f64>,
+    vel: DVector<f64>,
+    random_steps: Vec<RandomStep>,
+}
+
+/// Ground truth scenario built from segments run back to back, e.g.
+/// Trajectory::new(&[0.0, 0.0], &[50.0, 0.0])
+///     .then(Segment::ConstantVelocity { duration_s: 10.0 })
+///     .then(Segment::CoordinatedTurn { duration_s: 15.0, turn_rate_rad_s: 0.1 })
+/// Past the last segment the target holds its final velocity.
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    initial_pos: DVector<f64>,
+    initial_vel: DVector<f64>,
+    segments: Vec<Segment>,
+    segment_starts: Vec<SegmentStart>,
+}
+
+impl Trajectory {
+    /// initial_pos, initial_vel: one element per dimension, 1 to 3 dimensions
+    pub fn new(initial_pos: &[f64], initial_vel: &[f64]) -> Self {
+        assert!(
+            (1..=3).contains(&initial_pos.len()) && (initial_pos.len() == initial_vel.len()),
+            "Trajectory: Position and velocity must both have 1, 2 or 3 dimensions."
+        );
+
+        Self {
+            initial_pos: DVector::from_column_slice(initial_pos),
+            initial_vel: DVector::from_column_slice(initial_vel),
+            segments: Vec::new(),
+            segment_starts: Vec::new(),
+        }
+    }
+
+    /// Add the next segment
+    pub fn then(mut self, segment: Segment) -> Self {
+        let num_dims = self.get_num_dims();
+        assert!(
+            segment.get_duration_s() >= 0.0,
+            "Trajectory: Segment duration must not be negative."
+        );
+        match &segment {
+            Segment::ConstantAcceleration { acc: values, .. }
+            | Segment::Sinusoid {
+                amplitude: values, ..
+            }
+            | Segment::VelocityStep {
+                delta_vel: values, ..
+            } => assert!(
+                values.len() == num_dims,
+                "Trajectory: Segment has {} dimensions, trajectory has {}.",
+                values.len(),
+                num_dims
+            ),
+            Segment::CoordinatedTurn { .. } => assert!(
+                num_dims >= 2,
+                "Trajectory: Coordinated turns need a 2D or 3D trajectory."
+            ),
+            Segment::RandomVelocity {
+                step_s,
+                vel_std,
+                pos_std,
+                ..
+            } => assert!(
+                (*step_s > 0.0)
+                    && (vel_std.len() == num_dims)
+                    && (pos_std.len() == num_dims)
+                    && vel_std.iter().chain(pos_std).all(|&std| std >= 0.0),
+                "Trajectory: Random velocity needs a positive step and one non-negative \
+                 std dev per dimension."
+            ),
+            Segment::ConstantVelocity { .. } => {}
+        }
+
+        let (time_s, pos, vel) = self.get_end_state();
+        let random_steps = segment.draw_random_steps(&pos, &vel);
+        self.segments.push(segment);
+        self.segment_starts.push(SegmentStart {
+            time_s,
+            pos,
+            vel,
+            random_steps,
+        });
+        self
+    }
+
+    pub fn get_num_dims(&self) -> usize {
+        self.initial_pos.len()
+    }
+
+    /// Total duration of all segments [s]
+    pub fn get_duration_s(&self) -> f64 {
+        self.segments.iter().map(Segment::get_duration_s).sum()
+    }
+
+    /// Motion at time_s; before zero the target is at its initial state
+    pub fn state_at(&self, time_s: f64) -> TrajectoryPoint {
+        let time_s_clamped = time_s.max(0.0);
+
+        // Last segment starting at or before time_s
+        let segment_idx = self
+            .segment_starts
+            .iter()
+            .rposition(|start| start.time_s <= time_s_clamped);
+
+        let [pos, vel, acc] = match segment_idx {
+            Some(idx)
+                if time_s_clamped
+                    < self.segment_starts[idx].time_s + self.segments[idx].get_duration_s() =>
+            {
+                let start = &self.segment_starts[idx];
+                self.segments[idx].evaluate(
+                    &start.pos,
+                    &start.vel,
+                    &start.random_steps,
+                    time_s_clamped - start.time_s,
+                )
+            }
+            // Before the first segment or after the last, hold the velocity
+            _ => {
+                let (start_time_s, start_pos, start_vel) = self.get_end_state();
+                let tau = time_s_clamped - start_time_s;
+                [
+                    start_pos + &start_vel * tau.max(0.0),
+                    start_vel,
+                    DVector::zeros(self.get_num_dims()),
+                ]
+            }
+        };
+
+        TrajectoryPoint {
+            time_s,
+            pos,
+            vel,
+            acc,
+        }
+    }
+
+    /// Time history every dt seconds from zero to the end of the last segment
+    pub fn sample(&self, dt: f64) -> Vec<TrajectoryPoint> {
+        assert!(
+            dt > 0.0,
+            "Trajectory: Sample period must be greater than zero."
+        );
+
+        let num_samples = (self.get_duration_s() / dt).floor() as usize + 1;
+        (0..num_samples)
+            .map(|i| self.state_at(i as f64 * dt))
+            .collect()
+    }
+
+    /// Time, position and velocity at the end of the last segment
+    fn get_end_state(&self) -> (f64, DVector<f64>, DVector<f64>) {
+        match (self.segments.last(), self.segment_starts.last()) {
+            (Some(segment), Some(start)) => {
+                let duration_s = segment.get_duration_s();
+                let [pos, vel, _] =
+                    segment.evaluate(&start.pos, &start.vel, &start.random_steps, duration_s);
+                (start.time_s + duration_s, pos, vel)
+            }
+            _ => (0.0, self.initial_pos.clone(), self.initial_vel.clone()),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use plotters::prelude::*;
+
 pub fn ascending_float_range(start: f64, end: f64, step: f64) -> Vec<f64> {
     // Create an iterator that generates values from 'start' to 'end' with 'step' size
     let float_range: Vec<f64> = (0..)
@@ -5,7 +7,7 @@ pub fn ascending_float_range(start: f64, end: f64, step: f64) -> Vec<f64> {
         .take_while(|&x| x <= end) // Stop when the generated value exceeds 'end'
         .collect(); // Collect into a Vec
 
-    return float_range;
+    float_range
 }
 
 // TODO: This needs to take in a path buffer and handle printing errors
@@ -24,6 +26,21 @@ pub fn print_names_of_mat_file_arrays() {
     }
 }
 
+/// Read named, real valued arrays from a MATLAB .mat file, e.g. the textbook data files.
+/// Returns None if the file can't be opened or parsed, or an array is missing.
+pub fn read_mat_file_arrays(pathname: &str, array_names: &[&str]) -> Option<Vec<Vec<f64>>> {
+    let file = std::fs::File::open(pathname).ok()?;
+    let mat_file = matfile::MatFile::parse(file).ok()?;
+
+    array_names
+        .iter()
+        .map(|&name| match mat_file.find_by_name(name)?.data() {
+            matfile::NumericData::Double { real, .. } => Some(real.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
 pub struct PlotLabels {
     pub plot_pathname: String,
     pub title: String,
@@ -32,3 +49,280 @@ pub struct PlotLabels {
     pub y_axis_data1_label: String,
     pub y_axis_data2_label: String,
 }
+
+/// Plot two data series, e.g. true and estimated state, as red and blue lines against a
+/// shared x-axis. Axis ranges are fit to the data.
+pub fn plot_two_lines(
+    plot_labels: PlotLabels,
+    x_axis_data: &[f64],
+    y_axis_data1: &[f64],
+    y_axis_data2: &[f64],
+) {
+    let x_min = x_axis_data.iter().cloned().fold(f64::INFINITY, f64::min);
+    let x_max = x_axis_data
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y_min = y_axis_data1
+        .iter()
+        .chain(y_axis_data2.iter())
+        .cloned()
+        .fold(f64::INFINITY, f64::min);
+    let y_max = y_axis_data1
+        .iter()
+        .chain(y_axis_data2.iter())
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    // Pad the y-axis so the lines don't sit on the chart border
+    let y_pad = 0.05 * (y_max - y_min).max(1.0);
+
+    let root = BitMapBackend::new(&plot_labels.plot_pathname, (640, 480)).into_drawing_area();
+    let _ = root.fill(&WHITE);
+
+    // Configure the chart
+    let mut chart = ChartBuilder::on(&root)
+        .caption(plot_labels.title, ("sans-serif", 30).into_font())
+        .margin(25)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_min..x_max, (y_min - y_pad)..(y_max + y_pad))
+        .expect("ChartBuilder failed");
+
+    // Configure mesh with axis labels and grid lines
+    chart
+        .configure_mesh()
+        .x_labels(10)
+        .y_labels(10)
+        .x_desc(plot_labels.x_axis_label) // Label for the x-axis
+        .y_desc(plot_labels.y_axis_label) // Label for the y-axis
+        .x_label_style(("sans-serif", 18).into_font())
+        .y_label_style(("sans-serif", 18).into_font())
+        .x_label_formatter(&|x| format!("{}", *x as i64))
+        .y_label_formatter(&|y| format!("{:.1}", *y))
+        .draw()
+        .expect("configure_mesh() failed");
+
+    // Plot the first data series as a red line
+    chart
+        .draw_series(LineSeries::new(
+            x_axis_data
+                .iter()
+                .zip(y_axis_data1.iter())
+                .map(|(&x_val, &y_val)| (x_val, y_val)),
+            &RED,
+        ))
+        .unwrap_or_else(|_| {
+            panic!(
+                "draw_series() LineSeries {} failed",
+                plot_labels.y_axis_data1_label
+            )
+        })
+        .label(plot_labels.y_axis_data1_label)
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+
+    // Plot the second data series as a blue line
+    chart
+        .draw_series(LineSeries::new(
+            x_axis_data
+                .iter()
+                .zip(y_axis_data2.iter())
+                .map(|(&x_val, &y_val)| (x_val, y_val)),
+            &BLUE,
+        ))
+        .unwrap_or_else(|_| {
+            panic!(
+                "draw_series() LineSeries {} failed",
+                plot_labels.y_axis_data2_label
+            )
+        })
+        .label(plot_labels.y_axis_data2_label)
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .margin(5)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .expect("configure_series_labels() failed");
+
+    let _ = root.present();
+
+    println!("Plot written: {}", plot_labels.plot_pathname);
+}
+
+/// Plot any number of data series as colored lines against a shared x-axis, for comparisons
+/// that don't fit plot_two_lines. Axis ranges are fit to the data.
+/// lines: (legend label, y values) at each of x_axis_data
+pub fn plot_lines(
+    plot_pathname: &str,
+    title: &str,
+    x_axis_label: &str,
+    y_axis_label: &str,
+    x_axis_data: &[f64],
+    lines: &[(&str, Vec<f64>)],
+) {
+    const COLORS: [RGBColor; 4] = [RED, BLUE, GREEN, MAGENTA];
+
+    let x_min = x_axis_data.iter().cloned().fold(f64::INFINITY, f64::min);
+    let x_max = x_axis_data
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let y_min = lines
+        .iter()
+        .flat_map(|line| line.1.iter().cloned())
+        .fold(f64::INFINITY, f64::min);
+    let y_max = lines
+        .iter()
+        .flat_map(|line| line.1.iter().cloned())
+        .fold(f64::NEG_INFINITY, f64::max);
+    // Pad the y-axis so the lines don't sit on the chart border
+    let y_pad = 0.05 * (y_max - y_min).max(1.0);
+
+    let root = BitMapBackend::new(plot_pathname, (640, 480)).into_drawing_area();
+    let _ = root.fill(&WHITE);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", 30).into_font())
+        .margin(25)
+        .x_label_area_size(50)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_min..x_max, (y_min - y_pad)..(y_max + y_pad))
+        .expect("ChartBuilder failed");
+
+    chart
+        .configure_mesh()
+        .x_labels(10)
+        .y_labels(10)
+        .x_desc(x_axis_label)
+        .y_desc(y_axis_label)
+        .x_label_style(("sans-serif", 18).into_font())
+        .y_label_style(("sans-serif", 18).into_font())
+        .x_label_formatter(&|x| format!("{}", *x as i64))
+        .y_label_formatter(&|y| format!("{:.1}", *y))
+        .draw()
+        .expect("configure_mesh() failed");
+
+    for (line, color) in lines.iter().zip(COLORS.iter().cycle()) {
+        chart
+            .draw_series(LineSeries::new(
+                x_axis_data
+                    .iter()
+                    .zip(line.1.iter())
+                    .map(|(&x_val, &y_val)| (x_val, y_val)),
+                color,
+            ))
+            .unwrap_or_else(|_| panic!("draw_series() LineSeries {} failed", line.0))
+            .label(line.0)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .margin(5)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .expect("configure_series_labels() failed");
+
+    let _ = root.present();
+
+    println!("Plot written: {}", plot_pathname);
+}
+
+/// Bode plot, magnitude [dB] over phase [deg] against a log frequency axis, one colored
+/// line per filter response in each panel.
+/// responses: (legend label, magnitudes [dB], phases [deg]) at each of freqs_hz
+pub fn plot_bode(
+    plot_pathname: &str,
+    title: &str,
+    freqs_hz: &[f64],
+    responses: &[(&str, Vec<f64>, Vec<f64>)],
+) {
+    const COLORS: [RGBColor; 4] = [RED, BLUE, GREEN, MAGENTA];
+
+    let freq_min = freqs_hz.iter().cloned().fold(f64::INFINITY, f64::min);
+    let freq_max = freqs_hz.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    let root = BitMapBackend::new(plot_pathname, (640, 720)).into_drawing_area();
+    let _ = root.fill(&WHITE);
+    let root = root.titled(title, ("sans-serif", 30).into_font()).unwrap();
+    let panels = root.split_evenly((2, 1));
+
+    for (panel_idx, (panel, y_axis_label)) in panels
+        .iter()
+        .zip(["Magnitude [dB]", "Phase [deg]"])
+        .enumerate()
+    {
+        let panel_data = |response: &(&str, Vec<f64>, Vec<f64>)| {
+            if panel_idx == 0 {
+                response.1.clone()
+            } else {
+                response.2.clone()
+            }
+        };
+        // Clamp deep notches, e.g. moving average zeros, so they don't squash the chart
+        let y_min = responses
+            .iter()
+            .flat_map(panel_data)
+            .fold(f64::INFINITY, f64::min)
+            .max(if panel_idx == 0 { -60.0 } else { f64::MIN });
+        let y_max = responses
+            .iter()
+            .flat_map(panel_data)
+            .fold(f64::NEG_INFINITY, f64::max);
+        // Pad the y-axis so the lines don't sit on the chart border
+        let y_pad = 0.05 * (y_max - y_min).max(1.0);
+
+        let mut chart = ChartBuilder::on(panel)
+            .margin(15)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                (freq_min..freq_max).log_scale(),
+                (y_min - y_pad)..(y_max + y_pad),
+            )
+            .expect("ChartBuilder failed");
+
+        chart
+            .configure_mesh()
+            .x_desc("Frequency [Hz]")
+            .y_desc(y_axis_label)
+            .x_label_style(("sans-serif", 16).into_font())
+            .y_label_style(("sans-serif", 16).into_font())
+            .x_label_formatter(&|x| format!("{:.2}", *x))
+            .y_label_formatter(&|y| format!("{:.0}", *y))
+            .draw()
+            .expect("configure_mesh() failed");
+
+        for (response, color) in responses.iter().zip(COLORS.iter().cycle()) {
+            chart
+                .draw_series(LineSeries::new(
+                    freqs_hz
+                        .iter()
+                        .zip(panel_data(response))
+                        .map(|(&freq, y_val)| (freq, y_val.max(y_min - y_pad))),
+                    color,
+                ))
+                .unwrap_or_else(|_| panic!("draw_series() LineSeries {} failed", response.0))
+                .label(response.0)
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::LowerLeft)
+            .margin(5)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .expect("configure_series_labels() failed");
+    }
+
+    let _ = root.present();
+
+    println!("Plot written: {}", plot_pathname);
+}